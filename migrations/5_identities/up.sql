@@ -0,0 +1,13 @@
+-- Your SQL goes here
+create table identities
+(
+    id      INTEGER   not null
+        primary key autoincrement
+        unique,
+    user    INTEGER   not null
+        references users (id)
+            on delete cascade,
+    issuer  VARCHAR   not null,
+    subject VARCHAR   not null,
+    unique (issuer, subject)
+);
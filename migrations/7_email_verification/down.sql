@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users DROP COLUMN verification_token;
+ALTER TABLE users DROP COLUMN verified;
+ALTER TABLE users DROP COLUMN email;
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Existing users and users of external identity providers are considered verified.
+ALTER TABLE users ADD COLUMN email VARCHAR;
+ALTER TABLE users ADD COLUMN verified BOOLEAN not null default TRUE;
+ALTER TABLE users ADD COLUMN verification_token VARCHAR;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+create table api_tokens
+(
+    id        INTEGER  not null
+        primary key autoincrement
+        unique,
+    user      INTEGER  not null
+        references users (id)
+            on delete cascade,
+    name      VARCHAR  not null,
+    hash      CHAR(64) not null
+        unique,
+    scopes    VARCHAR  not null,
+    created   DATETIME default (datetime('now', 'localtime')) not null,
+    last_used DATETIME
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users DROP COLUMN unsubscribe_token;
+ALTER TABLE users DROP COLUMN last_digest;
+ALTER TABLE users DROP COLUMN digest;
+ALTER TABLE users DROP COLUMN email_answers;
+DROP TABLE followed_tags;
+DROP TABLE followed_questions;
\ No newline at end of file
@@ -0,0 +1,36 @@
+-- Your SQL goes here
+create table followed_questions
+(
+    id       INTEGER not null
+        primary key autoincrement
+        unique,
+    user     INTEGER not null
+        references users (id)
+            on delete cascade,
+    question INTEGER not null
+        references questions (id)
+            on delete cascade,
+    unique (user, question)
+);
+create table followed_tags
+(
+    id   INTEGER not null
+        primary key autoincrement
+        unique,
+    user INTEGER not null
+        references users (id)
+            on delete cascade,
+    tag  INTEGER not null
+        references tags (id)
+            on delete cascade,
+    unique (user, tag)
+);
+-- Which emails a user wants to receive, every email links to the unsubscribe token.
+ALTER TABLE users ADD COLUMN email_answers BOOLEAN not null default TRUE;
+ALTER TABLE users ADD COLUMN digest CHAR(8) not null default 'never';
+ALTER TABLE users ADD COLUMN last_digest DATETIME;
+ALTER TABLE users ADD COLUMN unsubscribe_token VARCHAR not null default '';
+UPDATE users SET unsubscribe_token = lower(hex(randomblob(16)));
+-- Askers and answerers follow their questions.
+INSERT OR IGNORE INTO followed_questions (user, question) SELECT author, id FROM questions;
+INSERT OR IGNORE INTO followed_questions (user, question) SELECT author, question FROM answers;
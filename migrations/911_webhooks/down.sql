@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE webhook_deliveries;
+DROP TABLE webhook_tags;
+DROP TABLE webhooks;
\ No newline at end of file
@@ -0,0 +1,45 @@
+-- Your SQL goes here
+create table webhooks
+(
+    id          INTEGER  not null
+        primary key autoincrement
+        unique,
+    url         VARCHAR  not null,
+    secret      VARCHAR  not null,
+    on_question BOOLEAN  default TRUE not null,
+    on_answer   BOOLEAN  default TRUE not null,
+    on_accepted BOOLEAN  default TRUE not null,
+    active      BOOLEAN  default TRUE not null,
+    created     DATETIME default (datetime('now', 'localtime')) not null
+);
+-- A webhook with tags only fires for questions with at least one of them.
+create table webhook_tags
+(
+    id      INTEGER not null
+        primary key autoincrement
+        unique,
+    webhook INTEGER not null
+        references webhooks (id)
+            on delete cascade,
+    tag     INTEGER not null
+        references tags (id)
+            on delete cascade,
+    unique (webhook, tag)
+);
+create table webhook_deliveries
+(
+    id           INTEGER  not null
+        primary key autoincrement
+        unique,
+    webhook      INTEGER  not null
+        references webhooks (id)
+            on delete cascade,
+    event        CHAR(16) not null,
+    payload      TEXT     not null,
+    status       CHAR(16) default 'pending' not null,
+    attempts     INTEGER  default 0 not null,
+    next_attempt DATETIME default (datetime('now', 'localtime')) not null,
+    response     INTEGER,
+    error        TEXT,
+    created      DATETIME default (datetime('now', 'localtime')) not null
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users DROP COLUMN hide_ignored;
+ALTER TABLE users DROP COLUMN notify_questions;
+DROP TABLE ignored_tags;
+DROP TABLE followed_users;
\ No newline at end of file
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+create table followed_users
+(
+    id       INTEGER not null
+        primary key autoincrement
+        unique,
+    user     INTEGER not null
+        references users (id)
+            on delete cascade,
+    followee INTEGER not null
+        references users (id)
+            on delete cascade,
+    unique (user, followee)
+);
+-- Questions with an ignored tag are greyed out or hidden in the lists of the user.
+create table ignored_tags
+(
+    id   INTEGER not null
+        primary key autoincrement
+        unique,
+    user INTEGER not null
+        references users (id)
+            on delete cascade,
+    tag  INTEGER not null
+        references tags (id)
+            on delete cascade,
+    unique (user, tag)
+);
+ALTER TABLE users ADD COLUMN notify_questions BOOLEAN not null default TRUE;
+ALTER TABLE users ADD COLUMN hide_ignored BOOLEAN not null default FALSE;
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+create table collections
+(
+    id          INTEGER  not null
+        primary key autoincrement
+        unique,
+    user        INTEGER  not null
+        references users (id)
+            on delete cascade,
+    name        VARCHAR  not null,
+    description TEXT     default '' not null,
+    public      BOOLEAN  default FALSE not null,
+    created     DATETIME default (datetime('now', 'localtime')) not null
+);
+-- A bookmark of a question, or of one of its answers if `answer` is set.
+create table bookmarks
+(
+    id         INTEGER  not null
+        primary key autoincrement
+        unique,
+    collection INTEGER  not null
+        references collections (id)
+            on delete cascade,
+    question   INTEGER  not null
+        references questions (id)
+            on delete cascade,
+    answer     INTEGER
+        references answers (id)
+            on delete cascade,
+    position   INTEGER  not null,
+    created    DATETIME default (datetime('now', 'localtime')) not null
+);
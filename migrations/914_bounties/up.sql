@@ -0,0 +1,25 @@
+-- Your SQL goes here
+-- A bounty offered by `user` on a question until it `expires`.
+-- Once closed `answer` and `awarded` hold the answer it went to and the points it received, if any.
+create table bounties
+(
+    id       INTEGER  not null
+        primary key autoincrement
+        unique,
+    question INTEGER  not null
+        references questions (id)
+            on delete cascade,
+    user     INTEGER  not null
+        references users (id)
+            on delete cascade,
+    amount   INTEGER  not null,
+    created  DATETIME default (datetime('now', 'localtime')) not null,
+    expires  DATETIME not null,
+    status   CHAR(16) default 'open' not null,
+    answer   INTEGER
+        references answers (id)
+            on delete set null,
+    awarded  INTEGER
+);
+-- A question has at most one open bounty at a time
+create unique index bounties_open_question on bounties (question) where status = 'open';
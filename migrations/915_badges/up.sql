@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- The badges earned by users, `badge` is the key of a badge rule defined in the code.
+create table awards
+(
+    id      INTEGER  not null
+        primary key autoincrement
+        unique,
+    user    INTEGER  not null
+        references users (id)
+            on delete cascade,
+    badge   CHAR(32) not null,
+    awarded DATETIME default (datetime('now', 'localtime')) not null,
+    unique (user, badge)
+);
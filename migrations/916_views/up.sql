@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Views of a thread are counted in memory and added in batches.
+ALTER TABLE questions ADD COLUMN views INTEGER not null default 0;
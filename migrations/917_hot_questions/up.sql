@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- The cached ranking of the hot questions, recomputed periodically.
+create table hot_questions
+(
+    id       INTEGER  not null
+        primary key autoincrement
+        unique,
+    question INTEGER  not null
+        unique
+        references questions (id)
+            on delete cascade,
+    heat     DOUBLE   not null,
+    computed DATETIME default (datetime('now', 'localtime')) not null
+);
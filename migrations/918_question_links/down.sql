@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE related_questions;
+DROP TABLE question_links;
\ No newline at end of file
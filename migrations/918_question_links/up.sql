@@ -0,0 +1,30 @@
+-- Your SQL goes here
+-- A link from the text of a question, or one of its answers, to another question.
+create table question_links
+(
+    id     INTEGER not null
+        primary key autoincrement
+        unique,
+    source INTEGER not null
+        references questions (id)
+            on delete cascade,
+    target INTEGER not null
+        references questions (id)
+            on delete cascade,
+    unique (source, target)
+);
+-- Questions similar by their tags and terms, stored for both questions of a pair.
+create table related_questions
+(
+    id         INTEGER not null
+        primary key autoincrement
+        unique,
+    question   INTEGER not null
+        references questions (id)
+            on delete cascade,
+    related    INTEGER not null
+        references questions (id)
+            on delete cascade,
+    similarity DOUBLE  not null,
+    unique (question, related)
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE answers DROP COLUMN html;
+ALTER TABLE questions DROP COLUMN html;
\ No newline at end of file
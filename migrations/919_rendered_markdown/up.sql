@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- The markdown of questions and answers is rendered once when they are written, posts written before are rendered at startup.
+ALTER TABLE questions ADD COLUMN html TEXT not null default '';
+ALTER TABLE answers ADD COLUMN html TEXT not null default '';
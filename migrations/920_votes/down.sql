@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop table answer_votes;
+drop table question_votes;
\ No newline at end of file
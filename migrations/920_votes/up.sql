@@ -0,0 +1,32 @@
+-- Your SQL goes here
+-- Every user votes at most once on a question or answer, `value` is 1 or -1.
+-- The scores of the posts count the votes, including the ones cast before they were recorded.
+create table question_votes
+(
+    id       INTEGER not null
+        primary key autoincrement
+        unique,
+    user     INTEGER not null
+        references users (id)
+            on delete cascade,
+    question INTEGER not null
+        references questions (id)
+            on delete cascade,
+    value    INTEGER not null,
+    unique (user, question)
+);
+
+create table answer_votes
+(
+    id     INTEGER not null
+        primary key autoincrement
+        unique,
+    user   INTEGER not null
+        references users (id)
+            on delete cascade,
+    answer INTEGER not null
+        references answers (id)
+            on delete cascade,
+    value  INTEGER not null,
+    unique (user, answer)
+);
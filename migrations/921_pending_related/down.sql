@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+drop table pending_related;
\ No newline at end of file
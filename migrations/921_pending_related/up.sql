@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- Questions whose links and related questions are not stored yet, they are computed in the background.
+create table pending_related
+(
+    id       INTEGER not null
+        primary key autoincrement
+        unique,
+    question INTEGER not null
+        unique
+        references questions (id)
+            on delete cascade
+);
+-- The questions asked before related questions were stored
+insert into pending_related (question)
+select id
+from questions;
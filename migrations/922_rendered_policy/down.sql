@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+drop table rendered_policy;
\ No newline at end of file
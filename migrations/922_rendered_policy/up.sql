@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- The hash of the sanitization policy the stored HTML of the posts was rendered with, a single row.
+create table rendered_policy
+(
+    id   INTEGER not null
+        primary key autoincrement
+        unique,
+    hash TEXT    not null
+);
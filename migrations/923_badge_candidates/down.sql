@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+drop table badge_candidates;
\ No newline at end of file
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- Users whose questions or answers changed since badges were last awarded, a row per change.
+create table badge_candidates
+(
+    id   INTEGER not null
+        primary key autoincrement
+        unique,
+    user INTEGER not null
+        references users (id)
+            on delete cascade
+);
+-- Everyone who posted before is evaluated once
+insert into badge_candidates (user)
+select author
+from questions
+union
+select author
+from answers;
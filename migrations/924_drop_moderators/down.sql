@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+-- The former moderators cannot be told apart from the other users anymore.
\ No newline at end of file
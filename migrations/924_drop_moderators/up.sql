@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Moderators had no permissions of their own, they are users again.
+UPDATE users SET role = 'user' WHERE role = 'moderator';
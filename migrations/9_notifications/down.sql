@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users DROP COLUMN notify_votes;
+ALTER TABLE users DROP COLUMN notify_accepted;
+ALTER TABLE users DROP COLUMN notify_answers;
+DROP TABLE notifications;
\ No newline at end of file
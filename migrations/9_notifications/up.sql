@@ -0,0 +1,23 @@
+-- Your SQL goes here
+create table notifications
+(
+    id       INTEGER  not null
+        primary key autoincrement
+        unique,
+    user     INTEGER  not null
+        references users (id)
+            on delete cascade,
+    kind     CHAR(16) not null,
+    question INTEGER  not null
+        references questions (id)
+            on delete cascade,
+    answer   INTEGER
+        references answers (id)
+            on delete cascade,
+    created  DATETIME default (datetime('now', 'localtime')) not null,
+    read     BOOLEAN  default FALSE not null
+);
+-- Which kinds of notifications a user wants to receive.
+ALTER TABLE users ADD COLUMN notify_answers BOOLEAN not null default TRUE;
+ALTER TABLE users ADD COLUMN notify_accepted BOOLEAN not null default TRUE;
+ALTER TABLE users ADD COLUMN notify_votes BOOLEAN not null default TRUE;
@@ -0,0 +1,431 @@
+use crate::auth::hash_token;
+use crate::backend::RegistrationConfig;
+use crate::db::models::{Answer, DisplayQuestion, Login, Scope, Tag, WebhookEvent};
+use crate::db::DbConn;
+use crate::mail::Mail;
+use crate::ranking::{self, Sort};
+use crate::subscriptions;
+use crate::tag_suggestions;
+use crate::webhooks;
+use rocket::http::Status;
+use rocket::outcome::try_outcome;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::status::Created;
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The body of every failed API request.
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct ApiError {
+    pub(crate) status: u16,
+    pub(crate) error: String,
+}
+
+impl ApiError {
+    pub(crate) fn new(status: Status, error: &str) -> Self {
+        ApiError {
+            status: status.code,
+            error: error.into(),
+        }
+    }
+}
+
+impl From<(Status, String)> for ApiError {
+    fn from((status, error): (Status, String)) -> Self {
+        ApiError {
+            status: status.code,
+            error,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::from_code(self.status).unwrap_or(Status::InternalServerError);
+        Response::build_from(Json(self).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Turns all errors below `/api` into JSON bodies, e.g. unknown routes or malformed request bodies.
+#[catch(default)]
+pub(crate) fn catcher(status: Status, _request: &Request) -> ApiError {
+    ApiError::new(status, status.reason().unwrap_or("Unknown error"))
+}
+
+/// A user authenticated to use the API.
+/// Scripts authenticate with an `Authorization: Bearer <token>` header, browsers with the login cookie.
+/// Unlike `Login` the request fails with `401 Unauthorized` if neither is present.
+pub(crate) struct ApiUser {
+    login: Login,
+    /// The scopes granted to the token, `None` for users logged in through the cookie.
+    scopes: Option<Vec<Scope>>,
+}
+
+impl ApiUser {
+    /// Return the user if the request is allowed to act within the given scope.
+    pub(crate) fn require(&self, scope: Scope) -> Result<&Login, ApiError> {
+        match &self.scopes {
+            Some(scopes) if !scopes.contains(&scope) => {
+                let error = format!("This token lacks the {} scope", scope.as_str());
+                Err(ApiError::new(Status::Forbidden, &error))
+            }
+            _ => Ok(&self.login),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiUser {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = match request.headers().get_one("Authorization") {
+            Some(header) => match header.strip_prefix("Bearer ") {
+                Some(token) => token.trim(),
+                None => return Outcome::Failure((Status::Unauthorized, ())),
+            },
+            None => {
+                return match request.guard::<Login>().await {
+                    Outcome::Success(login) => Outcome::Success(ApiUser {
+                        login,
+                        scopes: None,
+                    }),
+                    _ => Outcome::Failure((Status::Unauthorized, ())),
+                }
+            }
+        };
+
+        let conn = try_outcome!(request.guard::<DbConn>().await);
+        match conn.token_login(hash_token(token)).await {
+            Ok((login, scopes)) => Outcome::Success(ApiUser {
+                login,
+                scopes: Some(Scope::parse_list(&scopes)),
+            }),
+            Err(_) => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// The request body to ask a new question.
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct AskBody {
+    title: String,
+    text: String,
+    #[serde(default)]
+    tags: Vec<i32>,
+}
+
+/// The request body to suggest tags for a question.
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct SuggestBody {
+    #[serde(default)]
+    title: String,
+    text: String,
+}
+
+/// The request body to answer a question.
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct AnswerBody {
+    text: String,
+}
+
+/// The request body to vote on a question or answer, `1` for an upvote and `-1` for a downvote.
+#[derive(Debug, Deserialize, ToSchema)]
+pub(crate) struct VoteBody {
+    value: i32,
+}
+
+impl VoteBody {
+    fn diff(&self) -> Result<i32, ApiError> {
+        vote_diff(self.value)
+    }
+}
+
+/// Check that a vote is either `1` or `-1`.
+pub(crate) fn vote_diff(value: i32) -> Result<i32, ApiError> {
+    match value {
+        1 | -1 => Ok(value),
+        _ => Err(ApiError::new(
+            Status::BadRequest,
+            "A vote must be either 1 or -1",
+        )),
+    }
+}
+
+pub(crate) fn require_text(field: &str, value: &str) -> Result<(), ApiError> {
+    if value.trim().is_empty() {
+        let error = format!("The {} must not be empty", field);
+        return Err(ApiError::new(Status::BadRequest, &error));
+    }
+    Ok(())
+}
+
+/// List the questions, optionally only those with one of the given tags.
+/// They are sorted with the newest first unless another order is given, `hot` only lists the hot questions.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "questions",
+    params(
+        ("tag" = Option<Vec<String>>, Query, description = "Names of tags to filter by"),
+        ("sort" = Option<String>, Query, description = "The order: `newest`, `views` or `hot`")
+    ),
+    responses(
+        (status = 200, description = "The questions in the given order", body = [DisplayQuestion]),
+        (status = 400, description = "An unknown tag was given", body = ApiError)
+    )
+)]
+#[get("/questions?<tag>&<sort>")]
+pub(crate) async fn questions(
+    conn: DbConn,
+    tag: Option<Vec<String>>,
+    sort: Option<Sort>,
+) -> ApiResult<Json<Vec<DisplayQuestion>>> {
+    let tag = tag.unwrap_or_default();
+    if !tag.is_empty() {
+        // Fail on unknown tags
+        conn.tags_with_names(tag.clone()).await?;
+    }
+    Ok(Json(
+        ranking::questions(&conn, tag, sort.unwrap_or_default()).await?,
+    ))
+}
+
+/// Get a single question.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "questions",
+    responses(
+        (status = 200, description = "The question", body = DisplayQuestion),
+        (status = 404, description = "The question does not exist", body = ApiError)
+    )
+)]
+#[get("/questions/<id>")]
+pub(crate) async fn question(conn: DbConn, id: i32) -> ApiResult<Json<DisplayQuestion>> {
+    Ok(Json(conn.question(id).await?))
+}
+
+/// Ask a new question, requires the `write` scope.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "questions",
+    request_body = AskBody,
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 201, description = "The new question", body = DisplayQuestion),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 403, description = "The token lacks the scope or the email is not verified", body = ApiError)
+    )
+)]
+#[post("/questions", data = "<body>")]
+pub(crate) async fn ask(
+    conn: DbConn,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
+    user: ApiUser,
+    body: Json<AskBody>,
+) -> ApiResult<Created<Json<DisplayQuestion>>> {
+    let user = user.require(Scope::Write)?;
+    registration.check_can_post(&conn, user).await?;
+    let AskBody { title, text, tags } = body.into_inner();
+    require_text("title", &title)?;
+    require_text("text", &text)?;
+
+    let new_id = conn.new_question(user.id, title, text, tags).await?;
+    webhooks::fire(&conn, mail, WebhookEvent::QuestionCreated, new_id, None).await;
+    let location = uri!("/api/v1", question(id = new_id)).to_string();
+    Ok(Created::new(location).body(Json(conn.question(new_id).await?)))
+}
+
+/// List the answers of a question, the highest voted first.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "answers",
+    responses(
+        (status = 200, description = "The answers", body = [Answer]),
+        (status = 404, description = "The question does not exist", body = ApiError)
+    )
+)]
+#[get("/questions/<id>/answers")]
+pub(crate) async fn answers(conn: DbConn, id: i32) -> ApiResult<Json<Vec<Answer>>> {
+    // Fail on unknown questions
+    conn.question(id).await?;
+    Ok(Json(conn.answers(id).await?))
+}
+
+/// Answer a question, requires the `write` scope.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "answers",
+    request_body = AnswerBody,
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 201, description = "The new answer", body = Answer),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 403, description = "The token lacks the scope or the email is not verified", body = ApiError),
+        (status = 404, description = "The question does not exist", body = ApiError)
+    )
+)]
+#[post("/questions/<id>/answers", data = "<body>")]
+pub(crate) async fn answer(
+    conn: DbConn,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
+    user: ApiUser,
+    id: i32,
+    body: Json<AnswerBody>,
+) -> ApiResult<Created<Json<Answer>>> {
+    let user = user.require(Scope::Write)?;
+    registration.check_can_post(&conn, user).await?;
+    let AnswerBody { text } = body.into_inner();
+    require_text("text", &text)?;
+
+    conn.question(id).await?;
+    let new_id = conn.new_answer(user.id, id, text).await?;
+    subscriptions::answer_emails(&conn, mail, user, new_id).await;
+    webhooks::fire(&conn, mail, WebhookEvent::AnswerCreated, id, Some(new_id)).await;
+    let answer = conn.answer(new_id).await?;
+    let location = uri!("/api/v1", answers(id = answer.question)).to_string();
+    Ok(Created::new(location).body(Json(answer)))
+}
+
+/// The user the request is authenticated as.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "users",
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 200, description = "The user", body = Login),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 403, description = "The token lacks the `read` scope", body = ApiError)
+    )
+)]
+#[get("/me")]
+pub(crate) async fn me(user: ApiUser) -> ApiResult<Json<Login>> {
+    Ok(Json(user.require(Scope::Read)?.clone()))
+}
+
+/// List all tags.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "tags",
+    responses((status = 200, description = "All tags", body = [Tag]))
+)]
+#[get("/tags")]
+pub(crate) async fn tags(conn: DbConn) -> ApiResult<Json<Vec<Tag>>> {
+    Ok(Json(conn.all_tags().await?))
+}
+
+/// Suggest tags for a question from the crates its markdown text uses, the best matching first.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "tags",
+    request_body = SuggestBody,
+    responses((status = 200, description = "The suggested tags", body = [Tag]))
+)]
+#[post("/tags/suggest", data = "<body>")]
+pub(crate) async fn suggest_tags(
+    conn: DbConn,
+    body: Json<SuggestBody>,
+) -> ApiResult<Json<Vec<Tag>>> {
+    let SuggestBody { title, text } = body.into_inner();
+    Ok(Json(tag_suggestions::suggest(
+        &title,
+        &text,
+        conn.all_tags().await?,
+    )))
+}
+
+/// Vote on a question, requires the `vote` scope.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "questions",
+    request_body = VoteBody,
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 200, description = "The question with its new score", body = DisplayQuestion),
+        (status = 400, description = "The vote is neither 1 nor -1", body = ApiError),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 404, description = "The question does not exist", body = ApiError)
+    )
+)]
+#[post("/questions/<id>/vote", data = "<body>")]
+pub(crate) async fn vote_question(
+    conn: DbConn,
+    user: ApiUser,
+    id: i32,
+    body: Json<VoteBody>,
+) -> ApiResult<Json<DisplayQuestion>> {
+    let user = user.require(Scope::Vote)?;
+    let diff = body.diff()?;
+    conn.question(id).await?;
+    conn.update_question_score(id, diff, user.id).await?;
+    Ok(Json(conn.question(id).await?))
+}
+
+/// Vote on an answer, requires the `vote` scope.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "answers",
+    request_body = VoteBody,
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 200, description = "The answer with its new score", body = Answer),
+        (status = 400, description = "The vote is neither 1 nor -1", body = ApiError),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 404, description = "The answer does not exist", body = ApiError)
+    )
+)]
+#[post("/answers/<id>/vote", data = "<body>")]
+pub(crate) async fn vote_answer(
+    conn: DbConn,
+    user: ApiUser,
+    id: i32,
+    body: Json<VoteBody>,
+) -> ApiResult<Json<Answer>> {
+    let user = user.require(Scope::Vote)?;
+    let diff = body.diff()?;
+    conn.answer(id).await?;
+    conn.update_answer_score(id, diff, user.id).await?;
+    Ok(Json(conn.answer(id).await?))
+}
+
+/// Accept an answer, only the author of the question may do so. Requires the `write` scope.
+#[utoipa::path(
+    context_path = "/api/v1",
+    tag = "answers",
+    security(("bearer" = []), ("cookie" = [])),
+    responses(
+        (status = 200, description = "The accepted answer", body = Answer),
+        (status = 401, description = "Nobody is logged in", body = ApiError),
+        (status = 403, description = "The user did not ask the question", body = ApiError),
+        (status = 404, description = "The answer does not exist", body = ApiError)
+    )
+)]
+#[post("/answers/<id>/accept")]
+pub(crate) async fn accept(
+    conn: DbConn,
+    mail: &State<Mail>,
+    user: ApiUser,
+    id: i32,
+) -> ApiResult<Json<Answer>> {
+    let user = user.require(Scope::Write)?;
+    let answer = conn.answer(id).await?;
+    if conn.mark_solved(id, user.id).await? {
+        webhooks::fire(
+            &conn,
+            mail,
+            WebhookEvent::AnswerAccepted,
+            answer.question,
+            Some(id),
+        )
+        .await;
+    }
+    Ok(Json(conn.answer(id).await?))
+}
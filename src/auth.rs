@@ -0,0 +1,367 @@
+use crate::db::models::{Login, Role};
+use crate::db::DbConn;
+use ldap3::{dn_escape, ldap_escape, LdapConnAsync, LdapError, Scope, SearchEntry};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Generate a random alphanumeric string, suitable as a secret token.
+pub(crate) fn random_token(len: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Hash a secret token for storing it in the database.
+/// Tokens are random and long, so a fast hash is sufficient unlike for passwords.
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// A source of truth for verifying the credentials entered into the login form.
+#[rocket::async_trait]
+pub(crate) trait Authenticator: Send + Sync {
+    /// Verify the credentials of the given user and return a logged in user on success.
+    async fn authenticate(
+        &self,
+        conn: &DbConn,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, (Status, String)>;
+}
+
+/// Authenticates users against the bcrypt hashes stored in the `users` table.
+pub(crate) struct LocalAuthenticator;
+
+#[rocket::async_trait]
+impl Authenticator for LocalAuthenticator {
+    async fn authenticate(
+        &self,
+        conn: &DbConn,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, (Status, String)> {
+        conn.login(username.to_string(), password.to_string()).await
+    }
+}
+
+/// The configuration of the LDAP directory, read from the `ldap` table in `Rocket.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct LdapConfig {
+    url: String,
+    /// The DN to bind with, `{}` is replaced with the entered username.
+    bind_dn: String,
+    /// The base DN to search the groups of a user in.
+    group_base: Option<String>,
+    /// The filter selecting the groups of a user, `{}` is replaced with the DN of the user.
+    #[serde(default = "default_group_filter")]
+    group_filter: String,
+    #[serde(default)]
+    admin_groups: Vec<String>,
+}
+
+fn default_group_filter() -> String {
+    "(member={})".into()
+}
+
+/// A directory users bind to, i.e. an LDAP server or a stub in the tests.
+#[rocket::async_trait]
+trait Directory: Send + Sync {
+    /// Bind with the given DN and password and return the `cn` of the groups found with the filter below the base,
+    /// or `None` if the directory rejected the credentials.
+    async fn bind(
+        &self,
+        dn: &str,
+        password: &str,
+        groups: Option<(&str, &str)>,
+    ) -> Result<Option<Vec<String>>, LdapError>;
+}
+
+/// The LDAP server at the given url.
+struct LdapServer(String);
+
+#[rocket::async_trait]
+impl Directory for LdapServer {
+    async fn bind(
+        &self,
+        dn: &str,
+        password: &str,
+        groups: Option<(&str, &str)>,
+    ) -> Result<Option<Vec<String>>, LdapError> {
+        let (conn, mut ldap) = LdapConnAsync::new(&self.0).await?;
+        ldap3::drive!(conn);
+
+        if ldap.simple_bind(dn, password).await?.success().is_err() {
+            return Ok(None);
+        }
+
+        let mut names = vec![];
+        if let Some((base, filter)) = groups {
+            let (entries, _) = ldap
+                .search(base, Scope::Subtree, filter, vec!["cn"])
+                .await?
+                .success()?;
+            names = entries
+                .into_iter()
+                .flat_map(|e| SearchEntry::construct(e).attrs.remove("cn"))
+                .flatten()
+                .collect();
+        }
+        ldap.unbind().await?;
+        Ok(Some(names))
+    }
+}
+
+/// Authenticates users by binding to an LDAP directory with their credentials.
+/// Users are created on their first login and members of the admin groups are made administrators.
+pub(crate) struct LdapAuthenticator {
+    config: LdapConfig,
+    directory: Box<dyn Directory>,
+}
+
+impl LdapAuthenticator {
+    pub(crate) fn new(config: LdapConfig) -> Self {
+        LdapAuthenticator {
+            directory: Box::new(LdapServer(config.url.clone())),
+            config,
+        }
+    }
+
+    /// The DN of the user with the given name.
+    fn user_dn(&self, username: &str) -> String {
+        self.config.bind_dn.replace("{}", &dn_escape(username))
+    }
+
+    /// The filter selecting the groups of the user with the given DN.
+    fn group_filter(&self, dn: &str) -> String {
+        self.config.group_filter.replace("{}", &ldap_escape(dn))
+    }
+
+    /// Bind as the given user and return the DN and the role of the user,
+    /// or `None` if the directory rejected the credentials.
+    async fn bind(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<(String, Role)>, LdapError> {
+        let dn = self.user_dn(username);
+        let filter = self.group_filter(&dn);
+        let search = self
+            .config
+            .group_base
+            .as_deref()
+            .map(|base| (base, &*filter));
+        let groups = match self.directory.bind(&dn, password, search).await? {
+            Some(groups) => groups,
+            None => return Ok(None),
+        };
+        let role = match groups.iter().any(|g| self.config.admin_groups.contains(g)) {
+            true => Role::Admin,
+            false => Role::User,
+        };
+        Ok(Some((dn, role)))
+    }
+}
+
+#[rocket::async_trait]
+impl Authenticator for LdapAuthenticator {
+    async fn authenticate(
+        &self,
+        conn: &DbConn,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, (Status, String)> {
+        // An empty password would result in an unauthenticated bind, which always succeeds.
+        if password.is_empty() {
+            return Err((Status::Unauthorized, "wrong password".into()));
+        }
+        let (dn, role) = self
+            .bind(username, password)
+            .await
+            .map_err(|e| {
+                warn!("LDAP authentication failed: {}", e);
+                (Status::BadGateway, "Directory error".to_string())
+            })?
+            .ok_or((Status::Unauthorized, "wrong password".into()))?;
+
+        let mut login = conn
+            .external_login(self.config.url.clone(), dn, username.to_string())
+            .await?;
+        if self.config.group_base.is_some() && login.role != role {
+            conn.set_role(login.id, role).await?;
+            login.role = role;
+        }
+        Ok(login)
+    }
+}
+
+/// The configured authenticators, tried in order until one accepts the credentials.
+pub(crate) struct Authenticators(Vec<Box<dyn Authenticator>>);
+
+impl Authenticators {
+    /// Verify the credentials with all authenticators and return the first logged in user.
+    /// If no authenticator accepts them, the error of the last one is returned.
+    pub(crate) async fn login(
+        &self,
+        conn: &DbConn,
+        username: &str,
+        password: &str,
+    ) -> Result<Login, (Status, String)> {
+        let mut result = Err((Status::Unauthorized, "wrong password".into()));
+        for authenticator in self.0.iter() {
+            result = authenticator.authenticate(conn, username, password).await;
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+}
+
+/// A fairing setting up the authenticators, the LDAP directory is only used if it is configured.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Authenticators", |rocket| async {
+        let mut authenticators: Vec<Box<dyn Authenticator>> = vec![];
+        if let Ok(config) = rocket.figment().extract_inner::<LdapConfig>("ldap") {
+            authenticators.push(Box::new(LdapAuthenticator::new(config)));
+        }
+        authenticators.push(Box::new(LocalAuthenticator));
+        rocket.manage(Authenticators(authenticators))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_rocket;
+    use std::sync::{Arc, Mutex};
+
+    const ALICE: &str = "uid=alice,ou=people,dc=example,dc=org";
+
+    /// A directory with the single user alice, recording the binds made to it.
+    #[derive(Clone, Default)]
+    struct Stub(Arc<Mutex<StubState>>);
+
+    #[derive(Default)]
+    struct StubState {
+        groups: Vec<String>,
+        unreachable: bool,
+        binds: Vec<(String, Option<(String, String)>)>,
+    }
+
+    #[rocket::async_trait]
+    impl Directory for Stub {
+        async fn bind(
+            &self,
+            dn: &str,
+            password: &str,
+            groups: Option<(&str, &str)>,
+        ) -> Result<Option<Vec<String>>, LdapError> {
+            let mut state = self.0.lock().unwrap();
+            if state.unreachable {
+                return Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into());
+            }
+            let groups = groups.map(|(base, filter)| (base.to_string(), filter.to_string()));
+            state.binds.push((dn.to_string(), groups));
+            Ok((dn == ALICE && password == "secret").then(|| state.groups.clone()))
+        }
+    }
+
+    fn authenticator(stub: &Stub) -> LdapAuthenticator {
+        LdapAuthenticator {
+            config: LdapConfig {
+                url: "ldap://stub".into(),
+                bind_dn: "uid={},ou=people,dc=example,dc=org".into(),
+                group_base: Some("ou=groups,dc=example,dc=org".into()),
+                group_filter: default_group_filter(),
+                admin_groups: vec!["admins".into()],
+            },
+            directory: Box::new(stub.clone()),
+        }
+    }
+
+    #[test]
+    fn escapes_usernames_in_the_dn() {
+        let ldap = authenticator(&Stub::default());
+        assert_eq!(ldap.user_dn("alice"), ALICE);
+        assert_eq!(
+            ldap.user_dn("alice,ou=admins"),
+            "uid=alice\\2cou\\3dadmins,ou=people,dc=example,dc=org"
+        );
+    }
+
+    #[test]
+    fn escapes_the_dn_in_the_group_filter() {
+        let ldap = authenticator(&Stub::default());
+        assert_eq!(ldap.group_filter(ALICE), format!("(member={})", ALICE));
+        assert_eq!(
+            ldap.group_filter("uid=*)(uid=*"),
+            "(member=uid=\\2a\\29\\28uid=\\2a)"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn rejects_empty_passwords_without_binding() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        let stub = Stub::default();
+        let result = authenticator(&stub).authenticate(&conn, "alice", "").await;
+        assert_eq!(result.unwrap_err().0, Status::Unauthorized);
+        assert!(stub.0.lock().unwrap().binds.is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn provisions_users_with_the_role_of_their_groups() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        let stub = Stub::default();
+        let ldap = authenticator(&stub);
+
+        let login = ldap.authenticate(&conn, "alice", "secret").await.unwrap();
+        assert_eq!(login.username, "alice");
+        assert_eq!(login.role, Role::User);
+        assert_eq!(
+            stub.0.lock().unwrap().binds,
+            vec![(
+                ALICE.to_string(),
+                Some((
+                    "ou=groups,dc=example,dc=org".to_string(),
+                    format!("(member={})", ALICE)
+                ))
+            )]
+        );
+
+        stub.0.lock().unwrap().groups = vec!["developers".into(), "admins".into()];
+        let admin = ldap.authenticate(&conn, "alice", "secret").await.unwrap();
+        assert_eq!(admin.id, login.id);
+        assert_eq!(admin.role, Role::Admin);
+        assert_eq!(conn.user(login.id).await.unwrap().role, Role::Admin);
+
+        stub.0.lock().unwrap().groups = vec![];
+        let user = ldap.authenticate(&conn, "alice", "secret").await.unwrap();
+        assert_eq!(user.role, Role::User);
+        assert_eq!(conn.user(login.id).await.unwrap().role, Role::User);
+    }
+
+    #[rocket::async_test]
+    async fn rejects_wrong_passwords_and_reports_directory_errors() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        let stub = Stub::default();
+        let ldap = authenticator(&stub);
+
+        let wrong = ldap.authenticate(&conn, "alice", "guess").await;
+        assert_eq!(wrong.unwrap_err().0, Status::Unauthorized);
+        let unknown = ldap.authenticate(&conn, "mallory", "secret").await;
+        assert_eq!(unknown.unwrap_err().0, Status::Unauthorized);
+
+        stub.0.lock().unwrap().unreachable = true;
+        let error = ldap.authenticate(&conn, "alice", "secret").await;
+        assert_eq!(error.unwrap_err().0, Status::BadGateway);
+    }
+}
@@ -1,11 +1,57 @@
-use crate::db::models::Login;
+use crate::auth::Authenticators;
+use crate::db::models::{Login, Role, WebhookEvent};
 use crate::db::DbConn;
+use crate::mail::Mail;
+use crate::subscriptions;
+use crate::webhooks;
+use rocket::fairing::AdHoc;
 use rocket::form::Form;
 use rocket::http::{Cookie, CookieJar, Status};
-use rocket::outcome::IntoOutcome;
+use rocket::outcome::{try_outcome, IntoOutcome};
 use rocket::request::{FromRequest, Outcome};
 use rocket::response::Redirect;
-use rocket::Request;
+use rocket::{Request, State};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// The restrictions of new accounts, read from the `registration` table in `Rocket.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct RegistrationConfig {
+    /// Whether an email address has to be given on registration.
+    #[serde(default)]
+    require_email: bool,
+    /// Whether users have to verify their email address before they can post.
+    #[serde(default)]
+    verify_before_posting: bool,
+}
+
+impl RegistrationConfig {
+    /// Fail if the user is not allowed to post questions or answers yet.
+    pub(crate) async fn check_can_post(
+        &self,
+        conn: &DbConn,
+        user: &Login,
+    ) -> Result<(), (Status, String)> {
+        if self.verify_before_posting && !conn.is_verified(user.id).await? {
+            return Err((
+                Status::Forbidden,
+                "Please verify your email address before posting".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A fairing reading the restrictions of new accounts into the managed state.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Registration", |rocket| async {
+        let config = rocket
+            .figment()
+            .extract_inner::<RegistrationConfig>("registration")
+            .unwrap_or_default();
+        rocket.manage(config)
+    })
+}
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for Login {
@@ -20,20 +66,47 @@ impl<'r> FromRequest<'r> for Login {
     }
 }
 
-#[derive(Debug, FromForm)]
+/// A logged in administrator.
+/// The role is read from the database on every request, so demoted users lose their rights at once.
+pub(crate) struct Admin(pub(crate) Login);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let login = try_outcome!(request.guard::<Login>().await);
+        let conn = try_outcome!(request.guard::<DbConn>().await);
+        match conn.user(login.id).await {
+            Ok(user) if user.role == Role::Admin => Outcome::Success(Admin(user)),
+            _ => Outcome::Failure((Status::Forbidden, ())),
+        }
+    }
+}
+
+#[derive(Debug, FromForm, ToSchema)]
 pub(crate) struct LoginForm<'r> {
     username: &'r str,
     password: &'r str,
 }
 
+#[utoipa::path(
+    tag = "forms",
+    request_body(content = LoginForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Logged in, redirects to the index page"),
+        (status = 401, description = "Wrong password")
+    )
+)]
 #[post("/login", data = "<login>")]
 pub(crate) async fn login(
     conn: DbConn,
+    authenticators: &State<Authenticators>,
     cookies: &CookieJar<'_>,
     login: Form<LoginForm<'_>>,
 ) -> Result<Redirect, (Status, String)> {
-    let login = conn
-        .login(login.username.to_string(), login.password.to_string())
+    let login = authenticators
+        .login(&conn, login.username, login.password)
         .await?;
     cookies.add_private(Cookie::new("User", serde_json::to_string(&login).unwrap()));
     Ok(Redirect::to("/"))
@@ -47,123 +120,230 @@ pub(crate) async fn logout(cookies: &CookieJar<'_>) -> Redirect {
     Redirect::to("/")
 }
 
-#[derive(FromForm)]
+#[derive(FromForm, ToSchema)]
 pub(crate) struct RegisterForm<'r> {
     username: &'r str,
     password: &'r str,
     password_repeat: &'r str,
+    email: Option<&'r str>,
 }
+#[utoipa::path(
+    tag = "forms",
+    request_body(content = RegisterForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Registered and logged in, redirects to the index page"),
+        (status = 400, description = "The passwords do not match or the username is taken")
+    )
+)]
 #[post("/register", data = "<register>")]
 pub(crate) async fn register(
     conn: DbConn,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
     cookies: &CookieJar<'_>,
     register: Form<RegisterForm<'_>>,
 ) -> Result<Redirect, (Status, String)> {
     if register.password != register.password_repeat {
         return Err((Status::BadRequest, "Passwords do not match!".into()));
     }
+    let email = register.email.map(str::trim).filter(|e| !e.is_empty());
+    match email {
+        None if registration.require_email => {
+            return Err((Status::BadRequest, "An email address is required".into()));
+        }
+        Some(e) if e.parse::<lettre::Address>().is_err() => {
+            return Err((Status::BadRequest, "Invalid email address".into()));
+        }
+        _ => {}
+    }
     let login = conn
-        .register(register.username.to_string(), register.password.to_string())
+        .register(
+            register.username.to_string(),
+            register.password.to_string(),
+            email.map(String::from),
+        )
         .await?;
+
+    send_verification(&conn, mail, &login).await?;
     cookies.add_private(Cookie::new("User", serde_json::to_string(&login).unwrap()));
     Ok(Redirect::to("/"))
 }
 
-#[derive(Debug, FromForm)]
+/// Send the link to verify the email address of a user, if it is not verified yet.
+pub(crate) async fn send_verification(
+    conn: &DbConn,
+    mail: &Mail,
+    login: &Login,
+) -> Result<(), (Status, String)> {
+    if let Some((address, verification)) = conn.pending_verification(login.id).await? {
+        let body = format!(
+            "Hello {},\n\nplease confirm your email address by opening the following link:\n{}\n",
+            login.username,
+            mail.link(&uri!(verify(token = verification)).to_string())
+        );
+        if let Err(e) = mail
+            .send(&address, "Confirm your email address", body)
+            .await
+        {
+            warn!("Could not send verification email: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[get("/verify/<token>")]
+pub(crate) async fn verify(
+    conn: DbConn,
+    cookies: &CookieJar<'_>,
+    token: String,
+) -> Result<Redirect, (Status, String)> {
+    let login = conn.verify_email(token).await?;
+    cookies.add_private(Cookie::new("User", serde_json::to_string(&login).unwrap()));
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Debug, FromForm, ToSchema)]
 pub(crate) struct AskForm {
     title: String,
     question: String,
     tags: Vec<i32>,
 }
 
+#[utoipa::path(
+    tag = "forms",
+    request_body(content = AskForm, content_type = "application/x-www-form-urlencoded"),
+    security(("cookie" = [])),
+    responses(
+        (status = 303, description = "Redirects to the new question"),
+        (status = 403, description = "The email address is not verified")
+    )
+)]
 #[post("/ask", data = "<question>")]
 pub(crate) async fn ask_question(
     conn: DbConn,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
     question: Form<AskForm>,
     user: Login,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
+    registration.check_can_post(&conn, &user).await?;
     let AskForm {
         title,
         question,
         tags,
     } = question.into_inner();
     let new_id = conn.new_question(user.id, title, question, tags).await?;
+    webhooks::fire(&conn, mail, WebhookEvent::QuestionCreated, new_id, None).await;
     Ok(Redirect::to(uri!(thread(id = new_id))))
 }
 
-#[derive(Debug, FromForm)]
+#[derive(Debug, FromForm, ToSchema)]
 pub(crate) struct AnswerForm {
     question: i32,
     text: String,
 }
 
+#[utoipa::path(
+    tag = "forms",
+    request_body(content = AnswerForm, content_type = "application/x-www-form-urlencoded"),
+    security(("cookie" = [])),
+    responses(
+        (status = 303, description = "Redirects to the answered question"),
+        (status = 403, description = "The email address is not verified")
+    )
+)]
 #[post("/answer", data = "<answer>")]
 pub(crate) async fn answer_question(
     conn: DbConn,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
     answer: Form<AnswerForm>,
     user: Login,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
+    registration.check_can_post(&conn, &user).await?;
     let AnswerForm { question, text } = answer.into_inner();
-    conn.new_answer(user.id, question, text).await?;
+    let new_id = conn.new_answer(user.id, question, text).await?;
+    subscriptions::answer_emails(&conn, mail, &user, new_id).await;
+    webhooks::fire(
+        &conn,
+        mail,
+        WebhookEvent::AnswerCreated,
+        question,
+        Some(new_id),
+    )
+    .await;
     Ok(Redirect::to(uri!(thread(id = question))))
 }
 
 #[get("/upvote/<qid>/<aid>")]
 pub(crate) async fn upvote_answer(
     conn: DbConn,
-    _user: Login,
+    user: Login,
     qid: i32,
     aid: i32,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
-    conn.update_answer_score(aid, 1).await?;
+    conn.update_answer_score(aid, 1, user.id).await?;
     Ok(Redirect::to(uri!(thread(id = qid))))
 }
 
 #[get("/downvote/<qid>/<aid>")]
 pub(crate) async fn downvote_answer(
     conn: DbConn,
-    _user: Login,
+    user: Login,
     qid: i32,
     aid: i32,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
-    conn.update_answer_score(aid, -1).await?;
+    conn.update_answer_score(aid, -1, user.id).await?;
     Ok(Redirect::to(uri!(thread(id = qid))))
 }
 
 #[get("/upvote/<qid>")]
 pub(crate) async fn upvote_question(
     conn: DbConn,
-    _user: Login,
+    user: Login,
     qid: i32,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
-    conn.update_question_score(qid, 1).await?;
+    conn.update_question_score(qid, 1, user.id).await?;
     Ok(Redirect::to(uri!(thread(id = qid))))
 }
 
 #[get("/downvote/<qid>")]
 pub(crate) async fn downvote_question(
     conn: DbConn,
-    _user: Login,
+    user: Login,
     qid: i32,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
-    conn.update_question_score(qid, -1).await?;
+    conn.update_question_score(qid, -1, user.id).await?;
     Ok(Redirect::to(uri!(thread(id = qid))))
 }
 
-#[get("/solved/<qid>/<aid>")]
+/// Accept an answer, only the author of the question may do so.
+#[get("/solved/<_qid>/<aid>")]
 pub(crate) async fn solve_question(
     conn: DbConn,
-    _user: Login,
-    qid: i32,
+    mail: &State<Mail>,
+    user: Login,
+    _qid: i32,
     aid: i32,
 ) -> Result<Redirect, (Status, String)> {
     use crate::frontend::rocket_uri_macro_thread;
-    conn.mark_solved(aid).await?;
-    Ok(Redirect::to(uri!(thread(id = qid))))
+    // The question is the one of the answer, whatever the URL says
+    let answer = conn.answer(aid).await?;
+    if conn.mark_solved(aid, user.id).await? {
+        webhooks::fire(
+            &conn,
+            mail,
+            WebhookEvent::AnswerAccepted,
+            answer.question,
+            Some(aid),
+        )
+        .await;
+    }
+    Ok(Redirect::to(uri!(thread(id = answer.question))))
 }
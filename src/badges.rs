@@ -0,0 +1,310 @@
+use crate::db::models::{Activity, Award, Login};
+use crate::db::{DbConn, TaskConn};
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use chrono::NaiveDateTime;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// How often the rules are evaluated to award new badges.
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How hard a badge is to earn.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Tier {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+/// The condition a user has to meet to earn a badge.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Asked at least this many questions.
+    Questions(usize),
+    /// Gave at least this many answers.
+    Answers(usize),
+    /// At least this many answers were accepted.
+    AcceptedAnswers(usize),
+    /// A question reached this score.
+    QuestionScore(i32),
+    /// An answer reached this score.
+    AnswerScore(i32),
+    /// Answered at least this many questions with the tag.
+    TagAnswers(&'static str, usize),
+}
+
+/// A badge users earn once they meet its rule, earned badges are never taken away.
+#[derive(Debug, Clone, Copy)]
+struct Badge {
+    /// Identifies the badge in the awards table, it must not change.
+    key: &'static str,
+    name: &'static str,
+    description: &'static str,
+    tier: Tier,
+    rule: Rule,
+}
+
+const BADGES: &[Badge] = &[
+    Badge {
+        key: "first-question",
+        name: "Curious",
+        description: "Asked a first question",
+        tier: Tier::Bronze,
+        rule: Rule::Questions(1),
+    },
+    Badge {
+        key: "first-answer",
+        name: "Helper",
+        description: "Gave a first answer",
+        tier: Tier::Bronze,
+        rule: Rule::Answers(1),
+    },
+    Badge {
+        key: "first-accepted",
+        name: "First Accepted Answer",
+        description: "Had an answer accepted",
+        tier: Tier::Bronze,
+        rule: Rule::AcceptedAnswers(1),
+    },
+    Badge {
+        key: "rocket-answers-5",
+        name: "Rocket Scientist",
+        description: "Answered 5 Rocket questions",
+        tier: Tier::Bronze,
+        rule: Rule::TagAnswers("Rocket", 5),
+    },
+    Badge {
+        key: "diesel-answers-5",
+        name: "Diesel Mechanic",
+        description: "Answered 5 Diesel questions",
+        tier: Tier::Bronze,
+        rule: Rule::TagAnswers("Diesel", 5),
+    },
+    Badge {
+        key: "handlebars-answers-5",
+        name: "Mustache",
+        description: "Answered 5 Handlebars questions",
+        tier: Tier::Bronze,
+        rule: Rule::TagAnswers("Handlebars", 5),
+    },
+    Badge {
+        key: "question-score-10",
+        name: "Good Question",
+        description: "Asked a question with a score of 10",
+        tier: Tier::Silver,
+        rule: Rule::QuestionScore(10),
+    },
+    Badge {
+        key: "answer-score-10",
+        name: "Good Answer",
+        description: "Gave an answer with a score of 10",
+        tier: Tier::Silver,
+        rule: Rule::AnswerScore(10),
+    },
+    Badge {
+        key: "accepted-10",
+        name: "Problem Solver",
+        description: "Had 10 answers accepted",
+        tier: Tier::Silver,
+        rule: Rule::AcceptedAnswers(10),
+    },
+    Badge {
+        key: "question-score-25",
+        name: "Great Question",
+        description: "Asked a question with a score of 25",
+        tier: Tier::Gold,
+        rule: Rule::QuestionScore(25),
+    },
+    Badge {
+        key: "answer-score-25",
+        name: "Great Answer",
+        description: "Gave an answer with a score of 25",
+        tier: Tier::Gold,
+        rule: Rule::AnswerScore(25),
+    },
+    Badge {
+        key: "accepted-50",
+        name: "Guru",
+        description: "Had 50 answers accepted",
+        tier: Tier::Gold,
+        rule: Rule::AcceptedAnswers(50),
+    },
+];
+
+/// Return the users appearing at least `min` times.
+fn at_least(authors: impl Iterator<Item = i32>, min: usize) -> HashSet<i32> {
+    let mut counts: HashMap<i32, usize> = HashMap::new();
+    for author in authors {
+        *counts.entry(author).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|&(_, count)| count >= min)
+        .map(|(author, _)| author)
+        .collect()
+}
+
+impl Rule {
+    /// Return the users meeting the rule.
+    fn holders(&self, activity: &Activity) -> HashSet<i32> {
+        let questions = activity.questions.iter();
+        let answers = activity.answers.iter();
+        match *self {
+            Rule::Questions(min) => at_least(questions.map(|q| q.author), min),
+            Rule::Answers(min) => at_least(answers.map(|a| a.author), min),
+            Rule::AcceptedAnswers(min) => {
+                at_least(answers.filter(|a| a.accepted).map(|a| a.author), min)
+            }
+            Rule::QuestionScore(score) => questions
+                .filter(|q| q.score >= score)
+                .map(|q| q.author)
+                .collect(),
+            Rule::AnswerScore(score) => answers
+                .filter(|a| a.score >= score)
+                .map(|a| a.author)
+                .collect(),
+            Rule::TagAnswers(tag, min) => {
+                // Several answers to the same question count once
+                let answered: HashSet<(i32, i32)> = answers
+                    .filter(|a| {
+                        activity
+                            .tags
+                            .get(&a.question)
+                            .map(|tags| tags.iter().any(|t| t == tag))
+                            .unwrap_or(false)
+                    })
+                    .map(|a| (a.author, a.question))
+                    .collect();
+                at_least(answered.into_iter().map(|(author, _)| author), min)
+            }
+        }
+    }
+}
+
+/// Evaluate all rules for the users whose posts changed and award the badges they newly earned.
+async fn evaluate(conn: &DbConn) -> Result<(), (Status, String)> {
+    let (activity, last_change) = match conn.activity().await? {
+        Some(changed) => changed,
+        None => return Ok(()),
+    };
+    let earned = BADGES
+        .iter()
+        .flat_map(|badge| {
+            badge
+                .rule
+                .holders(&activity)
+                .into_iter()
+                .map(move |user| (user, badge.key.to_string()))
+        })
+        .collect();
+    conn.award_badges(earned, last_change).await
+}
+
+/// A fairing starting the job that awards badges.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Badges", |rocket| {
+        Box::pin(async move {
+            let conn = match rocket.state::<TaskConn>() {
+                Some(conn) => conn.clone(),
+                None => {
+                    error!("Badges need a database connection");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(EVALUATION_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err((_, e)) = evaluate(&conn).await {
+                        warn!("Could not award badges: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
+
+/// A badge as earned by a user.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct EarnedBadge {
+    name: &'static str,
+    description: &'static str,
+    tier: Tier,
+    awarded: NaiveDateTime,
+}
+
+/// Return the badges of the given awards, the awards of badges that no longer exist are skipped.
+pub(crate) fn earned_badges(awards: Vec<Award>) -> Vec<EarnedBadge> {
+    awards
+        .into_iter()
+        .filter_map(|award| {
+            let badge = BADGES.iter().find(|b| b.key == award.badge)?;
+            Some(EarnedBadge {
+                name: badge.name,
+                description: badge.description,
+                tier: badge.tier,
+                awarded: award.awarded,
+            })
+        })
+        .collect()
+}
+
+/// A badge together with everyone who earned it.
+#[derive(Debug, Clone, Serialize)]
+struct BadgeView {
+    name: &'static str,
+    description: &'static str,
+    tier: Tier,
+    num_awards: usize,
+    awards: Vec<Award>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BadgesCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    badges: Vec<BadgeView>,
+}
+
+/// All badges and who earned them.
+#[get("/badges")]
+pub(crate) async fn badges(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    let mut awards: HashMap<String, Vec<Award>> = HashMap::new();
+    for award in conn.awards().await? {
+        awards.entry(award.badge.clone()).or_default().push(award);
+    }
+    Ok(Template::render(
+        "badges",
+        BadgesCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            badges: BADGES
+                .iter()
+                .map(|badge| {
+                    let awards = awards.remove(badge.key).unwrap_or_default();
+                    BadgeView {
+                        name: badge.name,
+                        description: badge.description,
+                        tier: badge.tier,
+                        num_awards: awards.len(),
+                        awards,
+                    }
+                })
+                .collect(),
+        },
+    ))
+}
@@ -0,0 +1,279 @@
+use crate::db::models::{Answer, Bounty, Login, NewBounty};
+use crate::db::{DbConn, TaskConn};
+use crate::frontend::rocket_uri_macro_thread;
+use chrono::Local;
+use rocket::fairing::AdHoc;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use serde::Serialize;
+use std::time::Duration;
+
+/// The points a user earns for each vote on their questions and answers.
+pub(crate) const POINTS_PER_VOTE: i64 = 10;
+/// The smallest bounty that can be offered.
+pub(crate) const MIN_BOUNTY: i32 = 50;
+/// The largest bounty that can be offered.
+pub(crate) const MAX_BOUNTY: i32 = 500;
+/// How long a bounty runs.
+const BOUNTY_DAYS: i64 = 7;
+/// The score an answer needs to receive half of an expired bounty.
+const MIN_HALF_AWARD_SCORE: i32 = 2;
+/// How often expired bounties are looked for.
+const SETTLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What the asker of a question may offer as a bounty on it.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct BountyOffer {
+    points: i64,
+    min: i32,
+    max: i32,
+}
+
+impl BountyOffer {
+    pub(crate) fn new(points: i64) -> Self {
+        BountyOffer {
+            points,
+            min: MIN_BOUNTY,
+            max: MAX_BOUNTY,
+        }
+    }
+}
+
+/// Choose the answer an expired bounty goes to and the points it receives.
+/// Only answers given after the bounty started by someone other than its offerer are eligible:
+/// An accepted one receives the full amount, otherwise the highest voted one receives half of it
+/// if it reached the minimal score, the oldest one if several are tied.
+fn automatic_award(bounty: &Bounty, answers: &[Answer]) -> Option<(i32, i32)> {
+    let eligible = answers
+        .iter()
+        .filter(|a| a.time >= bounty.created && a.author != bounty.offerer);
+    if let Some(accepted) = eligible.clone().find(|a| a.accepted) {
+        return Some((accepted.id, bounty.amount));
+    }
+    eligible
+        .filter(|a| a.score >= MIN_HALF_AWARD_SCORE)
+        .max_by(|a, b| a.score.cmp(&b.score).then(b.time.cmp(&a.time)))
+        .map(|a| (a.id, bounty.amount / 2))
+}
+
+/// Close all bounties that expired, awarding them according to the automatic rules.
+async fn settle(conn: &DbConn) -> Result<(), (Status, String)> {
+    for bounty in conn.expired_bounties(Local::now().naive_local()).await? {
+        let answers = conn.answers(bounty.question).await?;
+        conn.close_bounty(bounty.id, automatic_award(&bounty, &answers))
+            .await?;
+    }
+    Ok(())
+}
+
+/// A fairing starting the task that closes expired bounties.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Bounties", |rocket| {
+        Box::pin(async move {
+            let conn = match rocket.state::<TaskConn>() {
+                Some(conn) => conn.clone(),
+                None => {
+                    error!("Bounties need a database connection");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(SETTLE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err((_, e)) = settle(&conn).await {
+                        warn!("Could not settle bounties: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct BountyForm {
+    amount: i32,
+}
+
+/// Offer a bounty on an unanswered question, only its asker may do so.
+#[post("/q/<qid>/bounty", data = "<form>")]
+pub(crate) async fn start_bounty(
+    user: Login,
+    conn: DbConn,
+    qid: i32,
+    form: Form<BountyForm>,
+) -> Result<Redirect, (Status, String)> {
+    let amount = form.into_inner().amount;
+    let question = conn.question(qid).await?;
+    if question.author != user.username {
+        return Err((
+            Status::Forbidden,
+            "Only the asker may offer a bounty".into(),
+        ));
+    }
+    if question.answered {
+        return Err((
+            Status::BadRequest,
+            "The question already has an accepted answer".into(),
+        ));
+    }
+    if !(MIN_BOUNTY..=MAX_BOUNTY).contains(&amount) {
+        return Err((
+            Status::BadRequest,
+            format!(
+                "A bounty must be between {} and {} points",
+                MIN_BOUNTY, MAX_BOUNTY
+            ),
+        ));
+    }
+    conn.start_bounty(NewBounty {
+        question: qid,
+        user: user.id,
+        amount,
+        expires: Local::now().naive_local() + chrono::Duration::days(BOUNTY_DAYS),
+    })
+    .await?;
+    Ok(Redirect::to(uri!(thread(id = qid))))
+}
+
+/// Award the full open bounty of a question to one of its answers, only the offerer may do so.
+#[post("/q/<qid>/bounty/award/<answer>")]
+pub(crate) async fn award_bounty(
+    user: Login,
+    conn: DbConn,
+    qid: i32,
+    answer: i32,
+) -> Result<Redirect, (Status, String)> {
+    let bounty = conn
+        .open_bounty(qid)
+        .await?
+        .ok_or((Status::NotFound, "This question has no open bounty".into()))?;
+    if bounty.offerer != user.username {
+        return Err((
+            Status::Forbidden,
+            "Only the offerer may award a bounty".into(),
+        ));
+    }
+    if conn.answer(answer).await?.author == user.username {
+        return Err((
+            Status::BadRequest,
+            "You cannot award a bounty to yourself".into(),
+        ));
+    }
+    conn.close_bounty(bounty.id, Some((answer, bounty.amount)))
+        .await?;
+    Ok(Redirect::to(format!(
+        "{}#answer-{}",
+        uri!(thread(id = qid)),
+        answer
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::BountyStatus;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    const AMOUNT: i32 = 100;
+
+    fn at(minutes: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2021, 3, 1)
+            .and_then(|day| day.and_hms_opt(12, 0, 0))
+            .unwrap()
+            + chrono::Duration::minutes(minutes)
+    }
+
+    fn bounty() -> Bounty {
+        Bounty {
+            id: 1,
+            question: 1,
+            offerer: "asker".into(),
+            amount: AMOUNT,
+            created: at(0),
+            expires: at(BOUNTY_DAYS * 24 * 60),
+            status: BountyStatus::Open,
+            answer: None,
+            awarded: None,
+        }
+    }
+
+    fn answer(id: i32, author: &str, minutes: i64, score: i32, accepted: bool) -> Answer {
+        Answer {
+            id,
+            author: author.into(),
+            question: 1,
+            time: at(minutes),
+            score,
+            accepted,
+            text: String::new(),
+            html: String::new(),
+        }
+    }
+
+    #[test]
+    fn accepted_answer_receives_the_full_amount() {
+        let answers = [
+            answer(1, "alice", 10, 5, false),
+            answer(2, "bob", 20, 0, true),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((2, AMOUNT)));
+    }
+
+    #[test]
+    fn highest_voted_answer_receives_half() {
+        let answers = [
+            answer(1, "alice", 10, 3, false),
+            answer(2, "bob", 20, 4, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((2, AMOUNT / 2)));
+    }
+
+    #[test]
+    fn oldest_answer_wins_ties() {
+        let answers = [
+            answer(1, "alice", 20, 3, false),
+            answer(2, "bob", 10, 3, false),
+            answer(3, "carol", 30, 3, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((2, AMOUNT / 2)));
+    }
+
+    #[test]
+    fn answers_below_the_minimal_score_receive_nothing() {
+        let answers = [answer(1, "alice", 10, MIN_HALF_AWARD_SCORE - 1, false)];
+        assert_eq!(automatic_award(&bounty(), &answers), None);
+        let answers = [answer(1, "alice", 10, MIN_HALF_AWARD_SCORE, false)];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((1, AMOUNT / 2)));
+        assert_eq!(automatic_award(&bounty(), &[]), None);
+    }
+
+    #[test]
+    fn answers_from_before_the_bounty_are_not_eligible() {
+        let answers = [
+            answer(1, "alice", -10, 0, true),
+            answer(2, "bob", -5, 9, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), None);
+        let answers = [
+            answer(1, "alice", -10, 0, true),
+            answer(2, "bob", 0, 2, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((2, AMOUNT / 2)));
+    }
+
+    #[test]
+    fn answers_of_the_offerer_are_not_eligible() {
+        let answers = [
+            answer(1, "asker", 10, 0, true),
+            answer(2, "asker", 10, 9, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), None);
+        let answers = [
+            answer(1, "asker", 10, 0, true),
+            answer(2, "bob", 20, 2, false),
+        ];
+        assert_eq!(automatic_award(&bounty(), &answers), Some((2, AMOUNT / 2)));
+    }
+}
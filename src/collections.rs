@@ -0,0 +1,227 @@
+use crate::db::models::{Bookmark, Collection, Login, NewCollection};
+use crate::db::DbConn;
+use crate::frontend::rocket_uri_macro_thread;
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+
+/// A collection together with the number of its bookmarks.
+#[derive(Debug, Clone, Serialize)]
+struct CollectionSummary {
+    collection: Collection,
+    size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CollectionsCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    owner: String,
+    /// Whether the collections belong to the current user, who also sees the private ones.
+    own: bool,
+    collections: Vec<CollectionSummary>,
+}
+
+#[get("/u/<name>/collections")]
+pub(crate) async fn collections(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    name: String,
+) -> Result<Template, (Status, String)> {
+    let owner = conn.user_with_name(name).await?;
+    let own = user.as_ref().map(|u| u.id == owner.id).unwrap_or(false);
+    let collections = conn.collections_of_user(owner.id, own).await?;
+    let sizes = conn
+        .collection_sizes(collections.iter().map(|c| c.id).collect())
+        .await?;
+    Ok(Template::render(
+        "collections",
+        CollectionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            owner: owner.username,
+            own,
+            collections: collections
+                .into_iter()
+                .map(|collection| CollectionSummary {
+                    size: sizes.get(&collection.id).copied().unwrap_or_default(),
+                    collection,
+                })
+                .collect(),
+        },
+    ))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct CollectionForm {
+    name: String,
+    description: String,
+    public: bool,
+}
+
+#[post("/collections", data = "<form>")]
+pub(crate) async fn create_collection(
+    user: Login,
+    conn: DbConn,
+    form: Form<CollectionForm>,
+) -> Result<Redirect, (Status, String)> {
+    let CollectionForm {
+        name,
+        description,
+        public,
+    } = form.into_inner();
+    if name.trim().is_empty() {
+        return Err((Status::BadRequest, "A collection needs a name".into()));
+    }
+    let new_id = conn
+        .new_collection(NewCollection {
+            user: user.id,
+            name,
+            description,
+            public,
+        })
+        .await?;
+    Ok(Redirect::to(uri!(collection(id = new_id))))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CollectionCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    owner: String,
+    own: bool,
+    collection: Collection,
+    bookmarks: Vec<Bookmark>,
+}
+
+/// A single collection, private ones are only shown to their owner.
+#[get("/c/<id>")]
+pub(crate) async fn collection(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    id: i32,
+) -> Result<Template, (Status, String)> {
+    let collection = conn.collection(id).await?;
+    let own = user
+        .as_ref()
+        .map(|u| u.id == collection.user)
+        .unwrap_or(false);
+    if !own && !collection.public {
+        return Err((Status::NotFound, "Collection not found".into()));
+    }
+    let owner = conn.user(collection.user).await?.username;
+    Ok(Template::render(
+        "collection",
+        CollectionCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            bookmarks: conn.bookmarks(collection.id).await?,
+            owner,
+            own,
+            collection,
+        },
+    ))
+}
+
+#[post("/c/<cid>/edit", data = "<form>")]
+pub(crate) async fn edit_collection(
+    user: Login,
+    conn: DbConn,
+    cid: i32,
+    form: Form<CollectionForm>,
+) -> Result<Redirect, (Status, String)> {
+    let CollectionForm {
+        name,
+        description,
+        public,
+    } = form.into_inner();
+    if name.trim().is_empty() {
+        return Err((Status::BadRequest, "A collection needs a name".into()));
+    }
+    conn.update_collection(user.id, cid, name, description, public)
+        .await?;
+    Ok(Redirect::to(uri!(collection(id = cid))))
+}
+
+#[post("/c/<id>/delete")]
+pub(crate) async fn delete_collection(
+    user: Login,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.delete_collection(user.id, id).await?;
+    Ok(Redirect::to(uri!(collections(name = user.username))))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct BookmarkForm {
+    collection: i32,
+    question: i32,
+    answer: Option<i32>,
+}
+
+#[post("/bookmarks", data = "<form>")]
+pub(crate) async fn bookmark(
+    user: Login,
+    conn: DbConn,
+    form: Form<BookmarkForm>,
+) -> Result<Redirect, (Status, String)> {
+    let BookmarkForm {
+        collection,
+        question,
+        answer,
+    } = form.into_inner();
+    conn.add_bookmark(user.id, collection, question, answer)
+        .await?;
+    let link = uri!(thread(id = question));
+    Ok(Redirect::to(match answer {
+        Some(answer) => format!("{}#answer-{}", link, answer),
+        None => link.to_string(),
+    }))
+}
+
+#[post("/c/<cid>/bookmarks/<bookmark>/up")]
+pub(crate) async fn move_up(
+    user: Login,
+    conn: DbConn,
+    cid: i32,
+    bookmark: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.move_bookmark(user.id, cid, bookmark, true).await?;
+    Ok(Redirect::to(uri!(collection(id = cid))))
+}
+
+#[post("/c/<cid>/bookmarks/<bookmark>/down")]
+pub(crate) async fn move_down(
+    user: Login,
+    conn: DbConn,
+    cid: i32,
+    bookmark: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.move_bookmark(user.id, cid, bookmark, false).await?;
+    Ok(Redirect::to(uri!(collection(id = cid))))
+}
+
+#[post("/c/<cid>/bookmarks/<bookmark>/remove")]
+pub(crate) async fn remove_bookmark(
+    user: Login,
+    conn: DbConn,
+    cid: i32,
+    bookmark: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.remove_bookmark(user.id, cid, bookmark).await?;
+    Ok(Redirect::to(uri!(collection(id = cid))))
+}
@@ -1,48 +1,321 @@
+use crate::auth::random_token;
+use crate::bounties::POINTS_PER_VOTE;
 use crate::db::models::{
-    Answer, DisplayQuestion, Login, NewAnswer, NewQuestion, NewUser, Question, Tag, User,
+    Activity, Answer, AnswerActivity, ApiToken, Award, Bookmark, Bounty, BountyStatus, Collection,
+    Delivery, DeliveryStatus, Digest, DisplayQuestion, EmailSettings, HeatInput, Login, NewAnswer,
+    NewApiToken, NewBounty, NewCollection, NewDelivery, NewIdentity, NewNotification, NewQuestion,
+    NewUser, NewWebhook, Notification, NotificationKind, NotificationSettings, Question,
+    QuestionActivity, QuestionLink, QuestionText, Role, Subscriber, Tag, User, Webhook,
+    WebhookEvent,
 };
 use crate::db::DbConn;
+use crate::live::{self, LiveEvent};
+use crate::mail;
+use crate::markdown::{self, Titles};
+use crate::related::{self, Document, RELATED_BATCH, RELATED_LIMIT};
 use bcrypt::verify;
+use chrono::{Local, NaiveDateTime};
 use diesel::expression::count::count_star;
 use diesel::result::{DatabaseErrorKind, Error};
 use diesel::{
-    insert_into, update, BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl,
-    RunQueryDsl,
+    delete, insert_into, insert_or_ignore_into, replace_into, update, BoolExpressionMethods,
+    Connection, ExpressionMethods, NullableExpressionMethods, OptionalExtension, QueryDsl,
+    RunQueryDsl, SqliteConnection,
 };
 use rocket::http::Status;
+use std::collections::{HashMap, HashSet};
+
+/// How many ids are passed to a query at once, older SQLite versions allow at most 999 parameters.
+const ID_CHUNK: usize = 500;
+/// How many posts are rendered to HTML again in one transaction.
+const RENDER_BATCH: i64 = 100;
 
 fn internal_error<E>(_: E) -> (Status, String) {
     (Status::InternalServerError, "Database error".into())
 }
 
+/// Notify a user about an event, unless they caused it themselves or do not want notifications of its kind.
+/// Called within the transaction storing the event.
+fn notify(
+    connection: &SqliteConnection,
+    actor: i32,
+    notification: NewNotification,
+) -> Result<(), Error> {
+    use crate::db::schema::notifications::dsl::notifications;
+    use crate::db::schema::users::dsl::{
+        id, notify_accepted, notify_answers, notify_questions, notify_votes, users,
+    };
+
+    if notification.user == actor {
+        return Ok(());
+    }
+    let settings: NotificationSettings = users
+        .filter(id.eq(notification.user))
+        .select((
+            notify_answers,
+            notify_accepted,
+            notify_votes,
+            notify_questions,
+        ))
+        .first(connection)?;
+    if settings.wants(notification.kind) {
+        insert_into(notifications)
+            .values(&notification)
+            .execute(connection)?;
+    }
+    Ok(())
+}
+
+/// Let a user follow a question, following it twice has no effect.
+fn follow(connection: &SqliteConnection, u_id: i32, q_id: i32) -> Result<(), Error> {
+    use crate::db::schema::followed_questions::dsl::{followed_questions, question, user};
+    insert_or_ignore_into(followed_questions)
+        .values((user.eq(u_id), question.eq(q_id)))
+        .execute(connection)?;
+    Ok(())
+}
+
+/// The points balance of a user: the score of their posts plus the bounties their answers received,
+/// minus the bounties they offered.
+fn points(connection: &SqliteConnection, u_id: i32) -> Result<i64, Error> {
+    use crate::db::schema::answers::dsl::{answers, author as answerer, id as answer_id, score};
+    use crate::db::schema::bounties::dsl::{amount, answer, awarded, bounties, user};
+    use crate::db::schema::questions::dsl::{author as asker, questions, score as question_score};
+    use diesel::expression::dsl::sum;
+
+    let asked: Option<i64> = questions
+        .filter(asker.eq(u_id))
+        .select(sum(question_score))
+        .first(connection)?;
+    let answered: Option<i64> = answers
+        .filter(answerer.eq(u_id))
+        .select(sum(score))
+        .first(connection)?;
+    let received: Option<i64> = bounties
+        .filter(
+            answer.eq_any(
+                answers
+                    .filter(answerer.eq(u_id))
+                    .select(answer_id.nullable()),
+            ),
+        )
+        .select(sum(awarded))
+        .first(connection)?;
+    let offered: Option<i64> = bounties
+        .filter(user.eq(u_id))
+        .select(sum(amount))
+        .first(connection)?;
+    Ok(
+        (asked.unwrap_or_default() + answered.unwrap_or_default()) * POINTS_PER_VOTE
+            + received.unwrap_or_default()
+            - offered.unwrap_or_default(),
+    )
+}
+
+/// Render markdown to HTML, showing references to other questions with their current titles.
+fn render_markdown(connection: &SqliteConnection, md: &str) -> Result<String, Error> {
+    use crate::db::schema::questions::dsl::{id, questions, title};
+    let referenced: Vec<i32> = markdown::references(md).into_iter().collect();
+    let titles: Titles = questions
+        .filter(id.eq_any(referenced))
+        .select((id, title))
+        .load::<(i32, String)>(connection)?
+        .into_iter()
+        .collect();
+    Ok(markdown::render(md, &titles))
+}
+
+/// Store the links from a question, or one of its answers, to the other questions mentioned in the text.
+fn store_links(connection: &SqliteConnection, q_id: i32, text: &str) -> Result<(), Error> {
+    use crate::db::schema::question_links::dsl::{question_links, source, target};
+    use crate::db::schema::questions::dsl::{id, questions};
+
+    let mentioned: Vec<i32> = related::linked_questions(text, mail::public_url())
+        .into_iter()
+        .filter(|&other| other != q_id)
+        .collect();
+    // Links to questions that do not exist are dropped
+    let existing: Vec<i32> = questions
+        .filter(id.eq_any(mentioned))
+        .select(id)
+        .load(connection)?;
+    for other in existing {
+        insert_or_ignore_into(question_links)
+            .values((source.eq(q_id), target.eq(other)))
+            .execute(connection)?;
+    }
+    Ok(())
+}
+
+/// Load all questions with their tags to compare them to each other.
+fn related_corpus(connection: &SqliteConnection) -> Result<Vec<Document>, Error> {
+    use crate::db::schema::chosen_tags::dsl::{chosen_tags, question, tag};
+    use crate::db::schema::questions::dsl::{id, questions, text, title};
+
+    let all: Vec<(i32, String, String)> = questions.select((id, title, text)).load(connection)?;
+    let mut tags: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for (tagged, t) in chosen_tags
+        .select((question, tag))
+        .load::<(i32, i32)>(connection)?
+    {
+        tags.entry(tagged).or_default().insert(t);
+    }
+    Ok(all
+        .iter()
+        .map(|(q, q_title, q_text)| {
+            Document::new(*q, q_title, q_text, tags.remove(q).unwrap_or_default())
+        })
+        .collect())
+}
+
+/// Remember that the posts of a user changed, so the badges they earned are evaluated again.
+fn mark_badge_candidate(connection: &SqliteConnection, u_id: i32) -> Result<(), Error> {
+    use crate::db::schema::badge_candidates::dsl::{badge_candidates, user};
+    insert_into(badge_candidates)
+        .values(user.eq(u_id))
+        .execute(connection)?;
+    Ok(())
+}
+
+/// Fail with `NotFound` unless the collection exists and belongs to the user.
+fn owned_collection(connection: &SqliteConnection, u_id: i32, c_id: i32) -> Result<(), Error> {
+    use crate::db::schema::collections::dsl::{collections, id, user};
+    collections
+        .filter(id.eq(c_id).and(user.eq(u_id)))
+        .select(id)
+        .first::<i32>(connection)?;
+    Ok(())
+}
+
 // Helper functions
 impl DbConn {
-    /// Converts multiple questions into a DisplayQuestions
+    /// Converts multiple questions into DisplayQuestions.
+    /// The tags, answers, bookmarks, bounties and views of all questions are loaded at once instead of querying them per question,
+    /// in chunks of ids small enough to be passed as parameters.
     async fn to_display_questions(
         &self,
         questions: Vec<Question>,
     ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
-        let mut res: Vec<DisplayQuestion> = Vec::with_capacity(questions.len());
-        for q in questions.into_iter() {
-            let dq = self.to_display_question(q).await?;
-            res.push(dq);
+        let ids: Vec<i32> = questions.iter().map(|q| q.id).collect();
+        let mut tags = HashMap::new();
+        let mut stats = HashMap::new();
+        let mut bookmarks = HashMap::new();
+        let mut bounties = HashMap::new();
+        let mut views = HashMap::new();
+        // SQLite limits the number of parameters of a query
+        for chunk in ids.chunks(ID_CHUNK) {
+            tags.extend(self.tags_of_questions(chunk.to_vec()).await?);
+            stats.extend(self.answer_stats(chunk.to_vec()).await?);
+            bookmarks.extend(self.bookmark_counts(chunk.to_vec()).await?);
+            bounties.extend(self.bounty_amounts(chunk.to_vec()).await?);
+            views.extend(self.view_counts(chunk.to_vec()).await?);
         }
-        Ok(res)
+
+        Ok(questions
+            .into_iter()
+            .map(|q| {
+                let (num_answers, answered) = stats.get(&q.id).copied().unwrap_or_default();
+                DisplayQuestion {
+                    tags: tags.remove(&q.id).unwrap_or_default(),
+                    id: q.id,
+                    author: q.author,
+                    time: q.time,
+                    score: q.score,
+                    title: q.title,
+                    text: q.text,
+                    html: q.html,
+                    num_answers,
+                    answered,
+                    bookmarks: bookmarks.get(&q.id).copied().unwrap_or_default(),
+                    bounty: bounties.get(&q.id).copied(),
+                    views: views.get(&q.id).copied().unwrap_or_default(),
+                }
+            })
+            .collect())
     }
 
     /// Annotate a Question with the data needed for displaying it, transforming it into a DisplayQuestion.
     async fn to_display_question(&self, q: Question) -> Result<DisplayQuestion, (Status, String)> {
-        Ok(DisplayQuestion {
-            id: q.id,
-            author: q.author,
-            time: q.time,
-            score: q.score,
-            title: q.title,
-            text: q.text,
-            tags: self.tags(q.id).await?,
-            num_answers: self.num_answers(q.id).await?,
-            answered: self.answered(q.id).await?,
-        })
+        let mut res = self.to_display_questions(vec![q]).await?;
+        Ok(res.remove(0))
+    }
+
+    /// Count the answers of the given questions and whether one of them was accepted.
+    async fn answer_stats(
+        &self,
+        q_ids: Vec<i32>,
+    ) -> Result<HashMap<i32, (i64, bool)>, (Status, String)> {
+        use crate::db::schema::answers::dsl::*;
+        let rows: Vec<(i32, bool)> = self
+            .run(move |connection| {
+                answers
+                    .filter(question.eq_any(q_ids))
+                    .select((question, accepted))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut stats: HashMap<i32, (i64, bool)> = HashMap::new();
+        for (q_id, is_accepted) in rows {
+            let entry = stats.entry(q_id).or_default();
+            entry.0 += 1;
+            entry.1 |= is_accepted;
+        }
+        Ok(stats)
+    }
+
+    /// Count how often the given questions were bookmarked, bookmarks of their answers are not counted.
+    async fn bookmark_counts(
+        &self,
+        q_ids: Vec<i32>,
+    ) -> Result<HashMap<i32, i64>, (Status, String)> {
+        use crate::db::schema::bookmarks::dsl::*;
+        let rows: Vec<i32> = self
+            .run(move |connection| {
+                bookmarks
+                    .filter(question.eq_any(q_ids).and(answer.is_null()))
+                    .select(question)
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut counts: HashMap<i32, i64> = HashMap::new();
+        for q_id in rows {
+            *counts.entry(q_id).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Return the amounts of the open bounties on the given questions.
+    async fn bounty_amounts(&self, q_ids: Vec<i32>) -> Result<HashMap<i32, i32>, (Status, String)> {
+        use crate::db::schema::bounties::dsl::*;
+        let rows: Vec<(i32, i32)> = self
+            .run(move |connection| {
+                bounties
+                    .filter(question.eq_any(q_ids).and(status.eq(BountyStatus::Open)))
+                    .select((question, amount))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Return how often the given questions were viewed.
+    async fn view_counts(&self, q_ids: Vec<i32>) -> Result<HashMap<i32, i32>, (Status, String)> {
+        use crate::db::schema::questions::dsl::*;
+        let rows: Vec<(i32, i32)> = self
+            .run(move |connection| {
+                questions
+                    .filter(id.eq_any(q_ids))
+                    .select((id, views))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        Ok(rows.into_iter().collect())
     }
 }
 
@@ -54,11 +327,12 @@ impl DbConn {
         login_name: String,
         login_pw: String,
     ) -> Result<Login, (Status, String)> {
-        use crate::db::schema::users::dsl::*;
+        use crate::db::schema::users::dsl::{id, password, role, username, users};
         let db_user: User = self
             .run(|connection| {
                 users
                     .filter(username.eq(login_name))
+                    .select((id, username, password, role))
                     .first::<User>(connection)
             })
             .await
@@ -75,10 +349,11 @@ impl DbConn {
         &self,
         username: String,
         password: String,
+        email: Option<String>,
     ) -> Result<Login, (Status, String)> {
         use crate::db::schema::users::dsl::users;
 
-        let user = NewUser::create(username.clone(), password.clone())
+        let user = NewUser::create(username.clone(), password.clone(), email)
             .map_err(|reason| (Status::BadRequest, reason))?;
 
         // Insert User into db
@@ -95,53 +370,183 @@ impl DbConn {
         self.login(username, password).await
     }
 
-    /// Return the number of answers a question has
-    pub(crate) async fn num_answers(&self, q_id: i32) -> Result<i64, (Status, String)> {
-        use crate::db::schema::answers::dsl::*;
+    /// Return the email address and the token of a user whose address is not verified yet.
+    pub(crate) async fn pending_verification(
+        &self,
+        u_id: i32,
+    ) -> Result<Option<(String, String)>, (Status, String)> {
+        use crate::db::schema::users::dsl::{email, id, users, verification_token, verified};
         self.run(move |connection| {
-            answers
-                .filter(question.eq(q_id))
-                .select(count_star())
-                .first::<i64>(connection)
+            users
+                .filter(id.eq(u_id).and(verified.eq(false)))
+                .select((email, verification_token))
+                .first::<(Option<String>, Option<String>)>(connection)
+        })
+        .await
+        .map(|(address, token)| address.zip(token))
+        .or_else(|e: Error| match e {
+            Error::NotFound => Ok(None),
+            e => Err(internal_error(e)),
+        })
+    }
+
+    /// Mark the email address of the user with the given token as verified and return the logged in user.
+    pub(crate) async fn verify_email(&self, token: String) -> Result<Login, (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let login = users
+                    .filter(verification_token.eq(&token))
+                    .select((id, username, role))
+                    .first::<Login>(connection)?;
+                update(users.filter(id.eq(login.id)))
+                    .set((verified.eq(true), verification_token.eq(None::<String>)))
+                    .execute(connection)?;
+                Ok(login)
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (Status::NotFound, "Invalid verification link".into()),
+            e => internal_error(e),
+        })
+    }
+
+    /// Return whether a user has verified their email address
+    pub(crate) async fn is_verified(&self, u_id: i32) -> Result<bool, (Status, String)> {
+        use crate::db::schema::users::dsl::{id, users, verified};
+        self.run(move |connection| {
+            users
+                .filter(id.eq(u_id))
+                .select(verified)
+                .first::<bool>(connection)
         })
         .await
         .map_err(internal_error)
     }
 
-    /// Return whether a question was answered
-    pub(crate) async fn answered(&self, q_id: i32) -> Result<bool, (Status, String)> {
-        use crate::db::schema::answers::dsl::*;
-        let res = self
+    /// Log in the user linked to an external identity.
+    /// On the first login of an identity a new user is created and linked to it.
+    pub(crate) async fn external_login(
+        &self,
+        ext_issuer: String,
+        ext_subject: String,
+        preferred_username: String,
+    ) -> Result<Login, (Status, String)> {
+        use crate::db::schema::identities::dsl::{identities, issuer, subject};
+        use crate::db::schema::users::dsl::{id, role, username, users};
+
+        let (lookup_issuer, lookup_subject) = (ext_issuer.clone(), ext_subject.clone());
+        let existing = self
             .run(move |connection| {
-                answers
-                    .filter(question.eq(q_id).and(accepted.eq(true)))
-                    .select(id)
-                    .first::<i32>(connection)
+                identities
+                    .inner_join(users)
+                    .filter(issuer.eq(lookup_issuer).and(subject.eq(lookup_subject)))
+                    .select((id, username, role))
+                    .first::<Login>(connection)
             })
             .await;
-
-        match res {
-            Ok(_) => Ok(true),
-            Err(Error::NotFound) => Ok(false),
-            Err(e) => Err(internal_error(e)),
+        match existing {
+            Ok(login) => return Ok(login),
+            Err(Error::NotFound) => {}
+            Err(e) => return Err(internal_error(e)),
         }
+
+        // External users never log in with a password, so a random one is stored.
+        let mut user = NewUser::create(preferred_username, random_token(32), None)
+            .map_err(|reason| (Status::BadRequest, reason))?;
+        // The provider is trusted to have verified its users.
+        user.verified = true;
+
+        // Insert the user under a free username and link the identity to it.
+        // A transaction is used to guarantee atomicity of the operations.
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let base = user.username.clone();
+                let mut suffix = 1;
+                while users
+                    .filter(username.eq(&user.username))
+                    .select(count_star())
+                    .first::<i64>(connection)?
+                    > 0
+                {
+                    suffix += 1;
+                    user.username = format!("{}-{}", base, suffix);
+                }
+                insert_into(users).values(&user).execute(connection)?;
+                let new_id = users.order_by(id.desc()).select(id).first(connection)?;
+                insert_into(identities)
+                    .values(NewIdentity {
+                        user: new_id,
+                        issuer: ext_issuer,
+                        subject: ext_subject,
+                    })
+                    .execute(connection)?;
+                Ok(Login {
+                    id: new_id,
+                    username: user.username,
+                    role: Role::User,
+                })
+            })
+        })
+        .await
+        .map_err(internal_error)
     }
 
-    /// Return all tags of a question
-    pub(crate) async fn tags(&self, q_id: i32) -> Result<Vec<Tag>, (Status, String)> {
-        use crate::db::schema::chosen_tags::dsl::{chosen_tags, question};
-        use crate::db::schema::tags::dsl::{description, id, name, tags};
+    /// Change the role of a user.
+    pub(crate) async fn set_role(&self, u_id: i32, new_role: Role) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::{id, role, users};
+
         self.run(move |connection| {
-            chosen_tags
-                .filter(question.eq(q_id))
-                .inner_join(tags)
-                .select((id, name, description))
-                .load::<Tag>(connection)
+            update(users.filter(id.eq(u_id)))
+                .set(role.eq(new_role))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return the users with the given names, unknown names are skipped.
+    pub(crate) async fn users_with_names(
+        &self,
+        names: Vec<String>,
+    ) -> Result<Vec<Login>, (Status, String)> {
+        use crate::db::schema::users::dsl::{id, role, username, users};
+        self.run(move |connection| {
+            users
+                .filter(username.eq_any(names))
+                .select((id, username, role))
+                .load(connection)
         })
         .await
         .map_err(internal_error)
     }
 
+    /// Return the user with the given id.
+    pub(crate) async fn user(&self, u_id: i32) -> Result<Login, (Status, String)> {
+        use crate::db::schema::users::dsl::{id, role, username, users};
+        self.run(move |connection| {
+            users
+                .filter(id.eq(u_id))
+                .select((id, username, role))
+                .first(connection)
+        })
+        .await
+        .map_err(|e| match e {
+            Error::NotFound => (Status::NotFound, "User not found".into()),
+            e => internal_error(e),
+        })
+    }
+
+    /// Return the user with the given name.
+    pub(crate) async fn user_with_name(&self, name: String) -> Result<Login, (Status, String)> {
+        self.users_with_names(vec![name])
+            .await?
+            .pop()
+            .ok_or((Status::NotFound, "User not found".into()))
+    }
+
     /// Return all tags in the database
     pub(crate) async fn all_tags(&self) -> Result<Vec<Tag>, (Status, String)> {
         use crate::db::schema::tags::dsl::tags;
@@ -168,6 +573,31 @@ impl DbConn {
         }
     }
 
+    /// Return the tags of the given questions, keyed by question.
+    pub(crate) async fn tags_of_questions(
+        &self,
+        q_ids: Vec<i32>,
+    ) -> Result<HashMap<i32, Vec<Tag>>, (Status, String)> {
+        use crate::db::schema::chosen_tags::dsl::{chosen_tags, question};
+        use crate::db::schema::tags::dsl::{description, id, name, tags};
+        let rows: Vec<(i32, Tag)> = self
+            .run(move |connection| {
+                chosen_tags
+                    .filter(question.eq_any(q_ids))
+                    .inner_join(tags)
+                    .select((question, (id, name, description)))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut res: HashMap<i32, Vec<Tag>> = HashMap::new();
+        for (q_id, tag) in rows {
+            res.entry(q_id).or_default().push(tag);
+        }
+        Ok(res)
+    }
+
     /// Select all questions in the database and oder them by newest first
     pub(crate) async fn newest_questions(&self) -> Result<Vec<DisplayQuestion>, (Status, String)> {
         use crate::db::schema::questions::dsl::*;
@@ -178,7 +608,30 @@ impl DbConn {
                 questions
                     .inner_join(users)
                     .order_by(time.desc())
-                    .select((id, username, time, score, title, text))
+                    .select((id, username, time, score, title, text, html))
+                    .load::<Question>(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        self.to_display_questions(new_questions).await
+    }
+
+    /// Return the given number of the newest questions, for feeds.
+    pub(crate) async fn latest_questions(
+        &self,
+        count: i64,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+
+        let new_questions: Vec<Question> = self
+            .run(move |connection| {
+                questions
+                    .inner_join(users)
+                    .order_by((time.desc(), id.desc()))
+                    .limit(count)
+                    .select((id, username, time, score, title, text, html))
                     .load::<Question>(connection)
             })
             .await
@@ -187,6 +640,35 @@ impl DbConn {
         self.to_display_questions(new_questions).await
     }
 
+    /// Return the given number of the newest questions with one of the given tags, for feeds.
+    pub(crate) async fn latest_questions_with_tag(
+        &self,
+        target_tags: Vec<String>,
+        count: i64,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::chosen_tags::dsl::chosen_tags;
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::tags::dsl::{name, tags};
+        use crate::db::schema::users::dsl::{username, users};
+
+        let tagged_questions: Vec<Question> = self
+            .run(move |connection| {
+                questions
+                    .inner_join(users)
+                    .inner_join(chosen_tags.inner_join(tags))
+                    .filter(name.eq_any(target_tags))
+                    .order_by((time.desc(), id.desc()))
+                    .select((id, username, time, score, title, text, html))
+                    .distinct()
+                    .limit(count)
+                    .load::<Question>(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        self.to_display_questions(tagged_questions).await
+    }
+
     /// Select all question that have a tag in the specified target vector.
     pub(crate) async fn questions_with_tag(
         &self,
@@ -204,7 +686,7 @@ impl DbConn {
                     .inner_join(chosen_tags.inner_join(tags))
                     .filter(name.eq_any(target_tags))
                     .order_by(time.desc())
-                    .select((id, username, time, score, title, text))
+                    .select((id, username, time, score, title, text, html))
                     .distinct()
                     .load::<Question>(connection)
             })
@@ -223,17 +705,23 @@ impl DbConn {
         tags: Vec<i32>,
     ) -> Result<i32, (Status, String)> {
         use crate::db::schema::chosen_tags::dsl::{chosen_tags, question, tag};
+        use crate::db::schema::followed_tags::dsl::{followed_tags, tag as watched_tag, user};
+        use crate::db::schema::pending_related::dsl::{
+            pending_related, question as pending_question,
+        };
         use crate::db::schema::questions::dsl::{id, questions};
 
-        let new_question = NewQuestion {
-            author,
-            title,
-            text,
-        };
-        // Insert question into db and retrieve id
+        // Render the question, insert it into db, retrieve id, let the author follow it,
+        // store its links, queue it for its related questions and notify the watchers of its tags.
         // A transaction is used to guarantee atomicity of the operations.
         self.run(move |connection| {
             connection.transaction::<_, Error, _>(|| {
+                let new_question = NewQuestion {
+                    author,
+                    title,
+                    html: render_markdown(connection, &text)?,
+                    text,
+                };
                 insert_into(questions)
                     .values(&new_question)
                     .execute(connection)?;
@@ -243,6 +731,29 @@ impl DbConn {
                         .values((question.eq(new_id), tag.eq(t)))
                         .execute(connection)?;
                 }
+                follow(connection, author, new_id)?;
+                mark_badge_candidate(connection, author)?;
+                store_links(connection, new_id, &new_question.text)?;
+                insert_into(pending_related)
+                    .values(pending_question.eq(new_id))
+                    .execute(connection)?;
+                let watchers: Vec<i32> = followed_tags
+                    .filter(watched_tag.eq_any(&tags))
+                    .select(user)
+                    .distinct()
+                    .load(connection)?;
+                for watcher in watchers {
+                    notify(
+                        connection,
+                        author,
+                        NewNotification {
+                            user: watcher,
+                            kind: NotificationKind::Question,
+                            question: new_id,
+                            answer: None,
+                        },
+                    )?;
+                }
                 Ok(new_id)
             })
         })
@@ -264,7 +775,7 @@ impl DbConn {
                 questions
                     .inner_join(users)
                     .filter(id.eq(qid))
-                    .select((id, username, time, score, title, text))
+                    .select((id, username, time, score, title, text, html))
                     .first(connection)
             })
             .await
@@ -275,6 +786,26 @@ impl DbConn {
         self.to_display_question(question).await
     }
 
+    /// Return the questions with the given ids, unknown ids are skipped.
+    pub(crate) async fn questions_with_ids(
+        &self,
+        q_ids: Vec<i32>,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        let found: Vec<Question> = self
+            .run(move |connection| {
+                questions
+                    .inner_join(users)
+                    .filter(id.eq_any(q_ids))
+                    .select((id, username, time, score, title, text, html))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        self.to_display_questions(found).await
+    }
+
     /// Select all answers of a given question
     pub(crate) async fn answers(&self, qid: i32) -> Result<Vec<Answer>, (Status, String)> {
         use crate::db::schema::answers::dsl::*;
@@ -284,100 +815,2055 @@ impl DbConn {
                 .inner_join(users)
                 .filter(question.eq(qid))
                 .order_by(score.desc())
-                .select((id, username, question, time, score, accepted, text))
+                .select((id, username, question, time, score, accepted, text, html))
                 .load(connection)
         })
         .await
         .map_err(internal_error)
     }
 
-    /// Add a new answer to the database.
-    pub(crate) async fn new_answer(
+    /// Return the given number of the newest answers to a question, for feeds.
+    pub(crate) async fn latest_answers(
         &self,
-        author: i32,
-        question: i32,
-        text: String,
-    ) -> Result<(), (Status, String)> {
-        use crate::db::schema::answers::dsl::answers;
-
-        let new = NewAnswer {
-            author,
-            question,
-            text,
-        };
-        self.run(move |connection| insert_into(answers).values(new).execute(connection))
-            .await
-            .map_err(|e: Error| match e {
-                Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
-                    (Status::BadRequest, "Invalid question id supplied".into())
-                }
-                e => internal_error(e),
-            })?;
-        Ok(())
+        qid: i32,
+        count: i64,
+    ) -> Result<Vec<Answer>, (Status, String)> {
+        use crate::db::schema::answers::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            answers
+                .inner_join(users)
+                .filter(question.eq(qid))
+                .order_by((time.desc(), id.desc()))
+                .limit(count)
+                .select((id, username, question, time, score, accepted, text, html))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
     }
 
-    /// Update the score of the question by the given difference.
-    pub(crate) async fn update_question_score(
+    /// Return the answers of all given questions, the highest voted first.
+    pub(crate) async fn answers_of_questions(
         &self,
-        q_id: i32,
-        diff: i32,
-    ) -> Result<(), (Status, String)> {
-        use crate::db::schema::questions::dsl::{id, questions, score};
+        q_ids: Vec<i32>,
+    ) -> Result<Vec<Answer>, (Status, String)> {
+        use crate::db::schema::answers::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            answers
+                .inner_join(users)
+                .filter(question.eq_any(q_ids))
+                .order_by(score.desc())
+                .select((id, username, question, time, score, accepted, text, html))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
 
+    /// Select an answer by id
+    pub(crate) async fn answer(&self, aid: i32) -> Result<Answer, (Status, String)> {
+        use crate::db::schema::answers::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
         self.run(move |connection| {
-            update(questions.filter(id.eq(q_id)))
-                .set(score.eq(score + diff))
-                .execute(connection)
+            answers
+                .inner_join(users)
+                .filter(id.eq(aid))
+                .select((id, username, question, time, score, accepted, text, html))
+                .first(connection)
         })
         .await
         .map_err(|e: Error| match e {
-            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
-                (Status::BadRequest, "Invalid question id supplied".into())
-            }
+            Error::NotFound => (Status::NotFound, "This answer does not exist".into()),
             e => internal_error(e),
-        })?;
-        Ok(())
+        })
     }
 
-    /// Update the score of the answer by the given difference.
-    pub(crate) async fn update_answer_score(
+    /// Add a new answer to the database and return the id of it.
+    pub(crate) async fn new_answer(
         &self,
-        a_id: i32,
-        diff: i32,
+        author: i32,
+        question: i32,
+        text: String,
+    ) -> Result<i32, (Status, String)> {
+        use crate::db::schema::answers::dsl::{answers, id};
+        use crate::db::schema::questions;
+
+        // Render the answer, insert it into db, retrieve id, follow the question, store its links and notify the asker
+        // A transaction is used to guarantee atomicity of the operations, viewers of the thread are told afterwards.
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let new = NewAnswer {
+                    author,
+                    question,
+                    html: render_markdown(connection, &text)?,
+                    text,
+                };
+                insert_into(answers).values(&new).execute(connection)?;
+                let new_id = answers.order_by(id.desc()).select(id).first(connection)?;
+                follow(connection, author, question)?;
+                mark_badge_candidate(connection, author)?;
+                store_links(connection, question, &new.text)?;
+                let asker = questions::table
+                    .filter(questions::id.eq(question))
+                    .select(questions::author)
+                    .first(connection)?;
+                notify(
+                    connection,
+                    author,
+                    NewNotification {
+                        user: asker,
+                        kind: NotificationKind::Answer,
+                        question,
+                        answer: Some(new_id),
+                    },
+                )?;
+                Ok(new_id)
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound | Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                (Status::BadRequest, "Invalid question id supplied".into())
+            }
+            e => internal_error(e),
+        })
+        .inspect(|&new_id| {
+            live::publish(LiveEvent::Answer {
+                question,
+                answer: new_id,
+            })
+        })
+    }
+
+    /// Record the vote of a user on a question, replacing their earlier one, and update its score.
+    /// Users cannot vote on their own questions.
+    pub(crate) async fn update_question_score(
+        &self,
+        q_id: i32,
+        diff: i32,
+        voter: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::question_votes::dsl::{question, question_votes, user, value};
+        use crate::db::schema::questions::dsl::{author, id, questions, score};
+
+        let new_score = self
+            .run(move |connection| {
+                connection.transaction::<_, Error, _>(|| {
+                    let asker: i32 = questions
+                        .filter(id.eq(q_id))
+                        .select(author)
+                        .first(connection)?;
+                    if asker == voter {
+                        return Ok(None);
+                    }
+                    let previous: i32 = question_votes
+                        .filter(user.eq(voter).and(question.eq(q_id)))
+                        .select(value)
+                        .first(connection)
+                        .optional()?
+                        .unwrap_or_default();
+                    // Voting the same way again changes nothing
+                    if diff != previous {
+                        replace_into(question_votes)
+                            .values((user.eq(voter), question.eq(q_id), value.eq(diff)))
+                            .execute(connection)?;
+                        update(questions.filter(id.eq(q_id)))
+                            .set(score.eq(score + diff - previous))
+                            .execute(connection)?;
+                        mark_badge_candidate(connection, asker)?;
+                        notify(
+                            connection,
+                            voter,
+                            NewNotification {
+                                user: asker,
+                                kind: NotificationKind::Vote,
+                                question: q_id,
+                                answer: None,
+                            },
+                        )?;
+                    }
+                    let new_score = questions
+                        .filter(id.eq(q_id))
+                        .select(score)
+                        .first(connection)?;
+                    Ok(Some(new_score))
+                })
+            })
+            .await
+            .map_err(|e: Error| match e {
+                Error::NotFound
+                | Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                    (Status::BadRequest, "Invalid question id supplied".into())
+                }
+                e => internal_error(e),
+            })?
+            .ok_or((
+                Status::Forbidden,
+                "You cannot vote on your own question".into(),
+            ))?;
+        live::publish(LiveEvent::Score {
+            question: q_id,
+            answer: None,
+            score: new_score,
+        });
+        Ok(())
+    }
+
+    /// Record the vote of a user on an answer, replacing their earlier one, and update its score.
+    /// Users cannot vote on their own answers.
+    pub(crate) async fn update_answer_score(
+        &self,
+        a_id: i32,
+        diff: i32,
+        voter: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::answer_votes::dsl::{answer, answer_votes, user, value};
+        use crate::db::schema::answers::dsl::{answers, author, id, question, score};
+
+        let (q_id, new_score) = self
+            .run(move |connection| {
+                connection.transaction::<_, Error, _>(|| {
+                    let (answerer, q_id): (i32, i32) = answers
+                        .filter(id.eq(a_id))
+                        .select((author, question))
+                        .first(connection)?;
+                    if answerer == voter {
+                        return Ok(None);
+                    }
+                    let previous: i32 = answer_votes
+                        .filter(user.eq(voter).and(answer.eq(a_id)))
+                        .select(value)
+                        .first(connection)
+                        .optional()?
+                        .unwrap_or_default();
+                    // Voting the same way again changes nothing
+                    if diff != previous {
+                        replace_into(answer_votes)
+                            .values((user.eq(voter), answer.eq(a_id), value.eq(diff)))
+                            .execute(connection)?;
+                        update(answers.filter(id.eq(a_id)))
+                            .set(score.eq(score + diff - previous))
+                            .execute(connection)?;
+                        mark_badge_candidate(connection, answerer)?;
+                        notify(
+                            connection,
+                            voter,
+                            NewNotification {
+                                user: answerer,
+                                kind: NotificationKind::Vote,
+                                question: q_id,
+                                answer: Some(a_id),
+                            },
+                        )?;
+                    }
+                    let new_score = answers
+                        .filter(id.eq(a_id))
+                        .select(score)
+                        .first(connection)?;
+                    Ok(Some((q_id, new_score)))
+                })
+            })
+            .await
+            .map_err(|e: Error| match e {
+                Error::NotFound
+                | Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                    (Status::BadRequest, "Invalid answer id supplied".into())
+                }
+                e => internal_error(e),
+            })?
+            .ok_or((
+                Status::Forbidden,
+                "You cannot vote on your own answer".into(),
+            ))?;
+        live::publish(LiveEvent::Score {
+            question: q_id,
+            answer: Some(a_id),
+            score: new_score,
+        });
+        Ok(())
+    }
+
+    /// Accept an answer in the name of the given user, who has to be the author of its question.
+    /// A previously accepted answer of the question is not accepted anymore.
+    /// Returns whether the answer was newly accepted, accepting it again changes nothing.
+    pub(crate) async fn mark_solved(&self, a_id: i32, u_id: i32) -> Result<bool, (Status, String)> {
+        use crate::db::schema::answers::dsl::{accepted, answers, author, id, question};
+        use crate::db::schema::questions;
+
+        let (q_id, changed) = self
+            .run(move |connection| {
+                connection.transaction::<_, Error, _>(|| {
+                    let (answerer, q_id, asker, was_accepted): (i32, i32, i32, bool) = answers
+                        .inner_join(questions::table)
+                        .filter(id.eq(a_id))
+                        .select((author, question, questions::author, accepted))
+                        .first(connection)?;
+                    if asker != u_id {
+                        return Ok(None);
+                    }
+                    if was_accepted {
+                        return Ok(Some((q_id, false)));
+                    }
+                    update(answers.filter(question.eq(q_id).and(accepted.eq(true))))
+                        .set(accepted.eq(false))
+                        .execute(connection)?;
+                    update(answers.filter(id.eq(a_id)))
+                        .set(accepted.eq(true))
+                        .execute(connection)?;
+                    mark_badge_candidate(connection, answerer)?;
+                    notify(
+                        connection,
+                        asker,
+                        NewNotification {
+                            user: answerer,
+                            kind: NotificationKind::Accepted,
+                            question: q_id,
+                            answer: Some(a_id),
+                        },
+                    )?;
+                    Ok(Some((q_id, true)))
+                })
+            })
+            .await
+            .map_err(|e: Error| match e {
+                Error::NotFound
+                | Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                    (Status::BadRequest, "Invalid answer id supplied".into())
+                }
+                e => internal_error(e),
+            })?
+            .ok_or((
+                Status::Forbidden,
+                "Only the author of the question may accept an answer".into(),
+            ))?;
+        if changed {
+            live::publish(LiveEvent::Accepted {
+                question: q_id,
+                answer: a_id,
+            });
+        }
+        Ok(changed)
+    }
+
+    /// Store a new API token of a user.
+    pub(crate) async fn new_api_token(&self, token: NewApiToken) -> Result<(), (Status, String)> {
+        use crate::db::schema::api_tokens::dsl::api_tokens;
+        self.run(move |connection| insert_into(api_tokens).values(token).execute(connection))
+            .await
+            .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return all API tokens of a user
+    pub(crate) async fn api_tokens(&self, u_id: i32) -> Result<Vec<ApiToken>, (Status, String)> {
+        use crate::db::schema::api_tokens::dsl::*;
+        self.run(move |connection| {
+            api_tokens
+                .filter(user.eq(u_id))
+                .order_by(created.desc())
+                .select((id, name, scopes, created, last_used))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Delete an API token of a user, so it can no longer be used.
+    pub(crate) async fn revoke_api_token(
+        &self,
+        u_id: i32,
+        t_id: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::api_tokens::dsl::{api_tokens, id, user};
+        let deleted = self
+            .run(move |connection| {
+                delete(api_tokens.filter(id.eq(t_id).and(user.eq(u_id)))).execute(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        if deleted == 0 {
+            return Err((Status::NotFound, "This token does not exist".into()));
+        }
+        Ok(())
+    }
+
+    /// Return the user and the scopes of the API token with the given hash and note its usage.
+    pub(crate) async fn token_login(
+        &self,
+        token_hash: String,
+    ) -> Result<(Login, String), (Status, String)> {
+        use crate::db::schema::api_tokens::dsl::{api_tokens, hash, id, last_used, scopes};
+        use crate::db::schema::users::dsl::{id as user_id, role, username, users};
+        self.run(move |connection| {
+            let (t_id, login, t_scopes) = api_tokens
+                .inner_join(users)
+                .filter(hash.eq(token_hash))
+                .select((id, (user_id, username, role), scopes))
+                .first::<(i32, Login, String)>(connection)?;
+            update(api_tokens.filter(id.eq(t_id)))
+                .set(last_used.eq(chrono::offset::Local::now().naive_local()))
+                .execute(connection)?;
+            Ok((login, t_scopes))
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (Status::Unauthorized, "Invalid API token".into()),
+            e => internal_error(e),
+        })
+    }
+
+    /// Return the newest notifications of a user.
+    pub(crate) async fn notifications(
+        &self,
+        u_id: i32,
+    ) -> Result<Vec<Notification>, (Status, String)> {
+        use crate::db::schema::notifications::dsl::*;
+        use crate::db::schema::questions::dsl::{questions, title};
+        self.run(move |connection| {
+            notifications
+                .inner_join(questions)
+                .filter(user.eq(u_id))
+                .order_by(id.desc())
+                .select((id, kind, question, title, answer, created, read))
+                .limit(100)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Count the unread notifications of a user.
+    pub(crate) async fn unread_notifications(&self, u_id: i32) -> Result<i64, (Status, String)> {
+        use crate::db::schema::notifications::dsl::*;
+        self.run(move |connection| {
+            notifications
+                .filter(user.eq(u_id).and(read.eq(false)))
+                .select(count_star())
+                .first(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Mark a notification of a user as read, or all of them if no id is given.
+    pub(crate) async fn mark_notifications_read(
+        &self,
+        u_id: i32,
+        n_id: Option<i32>,
     ) -> Result<(), (Status, String)> {
-        use crate::db::schema::answers::dsl::{answers, id, score};
+        use crate::db::schema::notifications::dsl::*;
+        self.run(move |connection| match n_id {
+            Some(n_id) => update(notifications.filter(user.eq(u_id).and(id.eq(n_id))))
+                .set(read.eq(true))
+                .execute(connection),
+            None => update(notifications.filter(user.eq(u_id)))
+                .set(read.eq(true))
+                .execute(connection),
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return which kinds of notifications a user wants to receive.
+    pub(crate) async fn notification_settings(
+        &self,
+        u_id: i32,
+    ) -> Result<NotificationSettings, (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            users
+                .filter(id.eq(u_id))
+                .select((
+                    notify_answers,
+                    notify_accepted,
+                    notify_votes,
+                    notify_questions,
+                ))
+                .first(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
 
+    /// Change which kinds of notifications a user wants to receive.
+    pub(crate) async fn set_notification_settings(
+        &self,
+        u_id: i32,
+        settings: NotificationSettings,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
         self.run(move |connection| {
-            update(answers.filter(id.eq(a_id)))
-                .set(score.eq(score + diff))
+            update(users.filter(id.eq(u_id)))
+                .set((
+                    notify_answers.eq(settings.answers),
+                    notify_accepted.eq(settings.accepted),
+                    notify_votes.eq(settings.votes),
+                    notify_questions.eq(settings.questions),
+                ))
                 .execute(connection)
         })
         .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return whether a user follows a question.
+    pub(crate) async fn follows_question(
+        &self,
+        u_id: i32,
+        q_id: i32,
+    ) -> Result<bool, (Status, String)> {
+        use crate::db::schema::followed_questions::dsl::*;
+        self.run(move |connection| {
+            followed_questions
+                .filter(user.eq(u_id).and(question.eq(q_id)))
+                .select(count_star())
+                .first::<i64>(connection)
+        })
+        .await
+        .map(|count| count > 0)
+        .map_err(internal_error)
+    }
+
+    /// Follow or unfollow a question.
+    pub(crate) async fn set_follows_question(
+        &self,
+        u_id: i32,
+        q_id: i32,
+        follows: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::followed_questions::dsl::*;
+        self.run(move |connection| {
+            if follows {
+                follow(connection, u_id, q_id)
+            } else {
+                delete(followed_questions.filter(user.eq(u_id).and(question.eq(q_id))))
+                    .execute(connection)
+                    .map(|_| ())
+            }
+        })
+        .await
         .map_err(|e: Error| match e {
             Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
-                (Status::BadRequest, "Invalid answer id supplied".into())
+                (Status::BadRequest, "Invalid question id supplied".into())
             }
             e => internal_error(e),
-        })?;
-        Ok(())
+        })
     }
 
-    /// Mark an answer as solved.
-    pub(crate) async fn mark_solved(&self, a_id: i32) -> Result<(), (Status, String)> {
-        use crate::db::schema::answers::dsl::{accepted, answers, id};
+    /// Return the ids of the tags a user follows.
+    pub(crate) async fn followed_tags(&self, u_id: i32) -> Result<Vec<i32>, (Status, String)> {
+        use crate::db::schema::followed_tags::dsl::*;
+        self.run(move |connection| {
+            followed_tags
+                .filter(user.eq(u_id))
+                .select(tag)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
 
+    /// Replace the tags a user follows.
+    pub(crate) async fn set_followed_tags(
+        &self,
+        u_id: i32,
+        tag_ids: Vec<i32>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::followed_tags::dsl::*;
         self.run(move |connection| {
-            update(answers.filter(id.eq(a_id)))
-                .set(accepted.eq(true))
-                .execute(connection)
+            connection.transaction::<_, Error, _>(|| {
+                delete(followed_tags.filter(user.eq(u_id))).execute(connection)?;
+                for t in tag_ids {
+                    insert_or_ignore_into(followed_tags)
+                        .values((user.eq(u_id), tag.eq(t)))
+                        .execute(connection)?;
+                }
+                Ok(())
+            })
         })
         .await
         .map_err(|e: Error| match e {
             Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
-                (Status::BadRequest, "Invalid answer id supplied".into())
+                (Status::BadRequest, "Invalid tag id supplied".into())
             }
             e => internal_error(e),
-        })?;
+        })
+    }
+
+    /// Return the email address of a user and which emails they want to receive.
+    pub(crate) async fn email_settings(
+        &self,
+        u_id: i32,
+    ) -> Result<EmailSettings, (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            users
+                .filter(id.eq(u_id))
+                .select((email, verified, email_answers, digest))
+                .first(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Change which emails a user wants to receive.
+    pub(crate) async fn set_email_settings(
+        &self,
+        u_id: i32,
+        answers: bool,
+        new_digest: Digest,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            update(users.filter(id.eq(u_id)))
+                .set((email_answers.eq(answers), digest.eq(new_digest)))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Change the email address of a user, the new address has to be verified again.
+    pub(crate) async fn change_email(
+        &self,
+        u_id: i32,
+        address: String,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            update(users.filter(id.eq(u_id)))
+                .set((
+                    email.eq(address),
+                    verified.eq(false),
+                    verification_token.eq(random_token(32)),
+                ))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Stop all emails to the user with the given unsubscribe token.
+    pub(crate) async fn unsubscribe(&self, token: String) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        let updated = self
+            .run(move |connection| {
+                update(users.filter(unsubscribe_token.eq(token)))
+                    .set((email_answers.eq(false), digest.eq(Digest::Never)))
+                    .execute(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        match updated {
+            0 => Err((Status::NotFound, "Invalid unsubscribe link".into())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return the users to email about a new answer to a question, i.e. its followers except the author.
+    pub(crate) async fn answer_subscribers(
+        &self,
+        q_id: i32,
+        author: i32,
+    ) -> Result<Vec<Subscriber>, (Status, String)> {
+        use crate::db::schema::followed_questions::dsl::{followed_questions, question};
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            followed_questions
+                .inner_join(users)
+                .filter(question.eq(q_id).and(id.ne(author)))
+                .filter(email.is_not_null().and(verified.eq(true)))
+                .filter(email_answers.eq(true))
+                .select((id, username, email, unsubscribe_token, digest, last_digest))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return all users that want to receive digests.
+    pub(crate) async fn digest_subscribers(&self) -> Result<Vec<Subscriber>, (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            users
+                .filter(digest.ne(Digest::Never))
+                .filter(email.is_not_null().and(verified.eq(true)))
+                .select((id, username, email, unsubscribe_token, digest, last_digest))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the questions other users asked since the given time with a tag the user follows.
+    pub(crate) async fn followed_tags_questions(
+        &self,
+        u_id: i32,
+        since: NaiveDateTime,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::chosen_tags::dsl::{chosen_tags, tag};
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+
+        let tag_ids = self.followed_tags(u_id).await?;
+        let new_questions: Vec<Question> = self
+            .run(move |connection| {
+                questions
+                    .inner_join(users)
+                    .inner_join(chosen_tags)
+                    .filter(tag.eq_any(tag_ids).and(time.gt(since)))
+                    .filter(author.ne(u_id))
+                    .order_by(time.desc())
+                    .select((id, username, time, score, title, text, html))
+                    .distinct()
+                    .load::<Question>(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        self.to_display_questions(new_questions).await
+    }
+
+    /// Remember when the last digest was sent to a user.
+    pub(crate) async fn mark_digest_sent(
+        &self,
+        u_id: i32,
+        sent: NaiveDateTime,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            update(users.filter(id.eq(u_id)))
+                .set(last_digest.eq(sent))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
         Ok(())
     }
+
+    /// Return all webhooks, the newest first.
+    pub(crate) async fn webhooks(&self) -> Result<Vec<Webhook>, (Status, String)> {
+        use crate::db::schema::webhooks::dsl::*;
+        self.run(move |connection| webhooks.order_by(id.desc()).load(connection))
+            .await
+            .map_err(internal_error)
+    }
+
+    /// Return a webhook by its id.
+    pub(crate) async fn webhook(&self, w_id: i32) -> Result<Webhook, (Status, String)> {
+        use crate::db::schema::webhooks::dsl::*;
+        self.run(move |connection| webhooks.filter(id.eq(w_id)).first(connection))
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => (Status::NotFound, "Webhook not found".into()),
+                e => internal_error(e),
+            })
+    }
+
+    /// Return the tags filtering each webhook, webhooks without tags are missing in the map.
+    pub(crate) async fn webhook_tags(&self) -> Result<HashMap<i32, Vec<Tag>>, (Status, String)> {
+        use crate::db::schema::tags::dsl::{description, id, name, tags};
+        use crate::db::schema::webhook_tags::dsl::{webhook, webhook_tags};
+        let rows: Vec<(i32, Tag)> = self
+            .run(move |connection| {
+                webhook_tags
+                    .inner_join(tags)
+                    .select((webhook, (id, name, description)))
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut res: HashMap<i32, Vec<Tag>> = HashMap::new();
+        for (w_id, tag) in rows {
+            res.entry(w_id).or_default().push(tag);
+        }
+        Ok(res)
+    }
+
+    /// Create a webhook only firing for questions with one of the given tags, or all questions if there are none.
+    pub(crate) async fn new_webhook(
+        &self,
+        new_webhook: NewWebhook,
+        tag_ids: Vec<i32>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhook_tags::dsl::{tag, webhook, webhook_tags};
+        use crate::db::schema::webhooks::dsl::{id, webhooks};
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                insert_into(webhooks)
+                    .values(new_webhook)
+                    .execute(connection)?;
+                let w_id: i32 = webhooks.select(id).order_by(id.desc()).first(connection)?;
+                for t in tag_ids {
+                    insert_or_ignore_into(webhook_tags)
+                        .values((webhook.eq(w_id), tag.eq(t)))
+                        .execute(connection)?;
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                (Status::BadRequest, "Invalid tag id supplied".into())
+            }
+            e => internal_error(e),
+        })
+    }
+
+    /// Delete a webhook together with its deliveries.
+    pub(crate) async fn delete_webhook(&self, w_id: i32) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhooks::dsl::*;
+        self.run(move |connection| delete(webhooks.filter(id.eq(w_id))).execute(connection))
+            .await
+            .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Pause or resume a webhook, paused webhooks do not enqueue new deliveries.
+    pub(crate) async fn set_webhook_active(
+        &self,
+        w_id: i32,
+        is_active: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhooks::dsl::*;
+        self.run(move |connection| {
+            update(webhooks.filter(id.eq(w_id)))
+                .set(active.eq(is_active))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Enqueue a delivery of the payload to every webhook fired on the event for a question with the given tags.
+    pub(crate) async fn enqueue_deliveries(
+        &self,
+        event: WebhookEvent,
+        question_tags: Vec<i32>,
+        payload: String,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhook_deliveries::dsl::webhook_deliveries;
+        use crate::db::schema::webhook_tags::dsl::{tag, webhook, webhook_tags};
+        use crate::db::schema::webhooks::dsl::webhooks;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let filters: Vec<(i32, i32)> =
+                    webhook_tags.select((webhook, tag)).load(connection)?;
+                for hook in webhooks.load::<Webhook>(connection)? {
+                    let mut hook_tags = filters.iter().filter(|(w, _)| *w == hook.id).peekable();
+                    let matches = hook_tags.peek().is_none()
+                        || hook_tags.any(|(_, t)| question_tags.contains(t));
+                    if hook.fires_on(event) && matches {
+                        insert_into(webhook_deliveries)
+                            .values(NewDelivery {
+                                webhook: hook.id,
+                                event,
+                                payload: payload.clone(),
+                            })
+                            .execute(connection)?;
+                    }
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the pending deliveries whose next attempt is due, together with their webhook.
+    pub(crate) async fn due_deliveries(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<Vec<(Delivery, Webhook)>, (Status, String)> {
+        use crate::db::schema::webhook_deliveries::dsl::*;
+        use crate::db::schema::webhooks::dsl::webhooks;
+        self.run(move |connection| {
+            webhook_deliveries
+                .inner_join(webhooks)
+                .filter(status.eq(DeliveryStatus::Pending).and(next_attempt.le(now)))
+                .order_by(next_attempt.asc())
+                .limit(50)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Store the outcome of an attempt to deliver a webhook.
+    pub(crate) async fn record_attempt(
+        &self,
+        d_id: i32,
+        new_status: DeliveryStatus,
+        next: NaiveDateTime,
+        new_response: Option<i32>,
+        new_error: Option<String>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhook_deliveries::dsl::*;
+        self.run(move |connection| {
+            update(webhook_deliveries.filter(id.eq(d_id)))
+                .set((
+                    status.eq(new_status),
+                    attempts.eq(attempts + 1),
+                    next_attempt.eq(next),
+                    response.eq(new_response),
+                    error.eq(new_error),
+                ))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return the latest deliveries of a webhook, the newest first.
+    pub(crate) async fn deliveries(&self, w_id: i32) -> Result<Vec<Delivery>, (Status, String)> {
+        use crate::db::schema::webhook_deliveries::dsl::*;
+        self.run(move |connection| {
+            webhook_deliveries
+                .filter(webhook.eq(w_id))
+                .order_by(id.desc())
+                .limit(100)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Enqueue a delivery again, starting over with its attempts.
+    pub(crate) async fn redeliver(&self, w_id: i32, d_id: i32) -> Result<(), (Status, String)> {
+        use crate::db::schema::webhook_deliveries::dsl::*;
+        self.run(move |connection| {
+            update(webhook_deliveries.filter(id.eq(d_id).and(webhook.eq(w_id))))
+                .set((
+                    status.eq(DeliveryStatus::Pending),
+                    attempts.eq(0),
+                    next_attempt.eq(Local::now().naive_local()),
+                ))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return the ids of the tags a user ignores.
+    pub(crate) async fn ignored_tags(&self, u_id: i32) -> Result<Vec<i32>, (Status, String)> {
+        use crate::db::schema::ignored_tags::dsl::*;
+        self.run(move |connection| {
+            ignored_tags
+                .filter(user.eq(u_id))
+                .select(tag)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Replace the tags a user ignores.
+    pub(crate) async fn set_ignored_tags(
+        &self,
+        u_id: i32,
+        tag_ids: Vec<i32>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::ignored_tags::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                delete(ignored_tags.filter(user.eq(u_id))).execute(connection)?;
+                for t in tag_ids {
+                    insert_or_ignore_into(ignored_tags)
+                        .values((user.eq(u_id), tag.eq(t)))
+                        .execute(connection)?;
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                (Status::BadRequest, "Invalid tag id supplied".into())
+            }
+            e => internal_error(e),
+        })
+    }
+
+    /// Return whether a user hides questions with ignored tags instead of greying them out.
+    pub(crate) async fn hides_ignored(&self, u_id: i32) -> Result<bool, (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            users
+                .filter(id.eq(u_id))
+                .select(hide_ignored)
+                .first(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Change whether a user hides questions with ignored tags.
+    pub(crate) async fn set_hides_ignored(
+        &self,
+        u_id: i32,
+        hide: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::users::dsl::*;
+        self.run(move |connection| {
+            update(users.filter(id.eq(u_id)))
+                .set(hide_ignored.eq(hide))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return whether a user follows another one.
+    pub(crate) async fn follows_user(
+        &self,
+        u_id: i32,
+        other: i32,
+    ) -> Result<bool, (Status, String)> {
+        use crate::db::schema::followed_users::dsl::*;
+        self.run(move |connection| {
+            followed_users
+                .filter(user.eq(u_id).and(followee.eq(other)))
+                .select(count_star())
+                .first::<i64>(connection)
+        })
+        .await
+        .map(|count| count > 0)
+        .map_err(internal_error)
+    }
+
+    /// Follow or unfollow another user.
+    pub(crate) async fn set_follows_user(
+        &self,
+        u_id: i32,
+        other: i32,
+        follows: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::followed_users::dsl::*;
+        if u_id == other {
+            return Err((Status::BadRequest, "You cannot follow yourself".into()));
+        }
+        self.run(move |connection| {
+            if follows {
+                insert_or_ignore_into(followed_users)
+                    .values((user.eq(u_id), followee.eq(other)))
+                    .execute(connection)
+            } else {
+                delete(followed_users.filter(user.eq(u_id).and(followee.eq(other))))
+                    .execute(connection)
+            }
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Count the followers of a user.
+    pub(crate) async fn num_followers(&self, u_id: i32) -> Result<i64, (Status, String)> {
+        use crate::db::schema::followed_users::dsl::*;
+        self.run(move |connection| {
+            followed_users
+                .filter(followee.eq(u_id))
+                .select(count_star())
+                .first(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Select the questions asked by a user, the newest first.
+    pub(crate) async fn questions_of_user(
+        &self,
+        u_id: i32,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+
+        let asked: Vec<Question> = self
+            .run(move |connection| {
+                questions
+                    .inner_join(users)
+                    .filter(author.eq(u_id))
+                    .order_by(time.desc())
+                    .select((id, username, time, score, title, text, html))
+                    .load::<Question>(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        self.to_display_questions(asked).await
+    }
+
+    /// Select the questions a user follows, asked by users they follow or tagged with tags they watch, the newest first.
+    pub(crate) async fn personal_feed(
+        &self,
+        u_id: i32,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::chosen_tags::dsl::{chosen_tags, question, tag};
+        use crate::db::schema::followed_questions::dsl::{
+            followed_questions, question as followed_question, user as question_follower,
+        };
+        use crate::db::schema::followed_tags::dsl::{
+            followed_tags, tag as watched_tag, user as tag_watcher,
+        };
+        use crate::db::schema::followed_users::dsl::{followed_users, followee, user};
+        use crate::db::schema::questions::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+
+        let feed: Vec<Question> = self
+            .run(move |connection| {
+                let followed = followed_questions
+                    .filter(question_follower.eq(u_id))
+                    .select(followed_question);
+                let followees = followed_users.filter(user.eq(u_id)).select(followee);
+                let watched = followed_tags
+                    .filter(tag_watcher.eq(u_id))
+                    .select(watched_tag);
+                let tagged = chosen_tags.filter(tag.eq_any(watched)).select(question);
+                questions
+                    .inner_join(users)
+                    .filter(
+                        id.eq_any(followed)
+                            .or(author.eq_any(followees))
+                            .or(id.eq_any(tagged)),
+                    )
+                    .order_by(time.desc())
+                    .select((id, username, time, score, title, text, html))
+                    .load::<Question>(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        self.to_display_questions(feed).await
+    }
+
+    /// Return the collections of a user, only the public ones unless `private` is set.
+    pub(crate) async fn collections_of_user(
+        &self,
+        u_id: i32,
+        private: bool,
+    ) -> Result<Vec<Collection>, (Status, String)> {
+        use crate::db::schema::collections::dsl::*;
+        self.run(move |connection| {
+            collections
+                .filter(user.eq(u_id).and(public.eq(true).or(private)))
+                .order_by(name.asc())
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return a collection by its id.
+    pub(crate) async fn collection(&self, c_id: i32) -> Result<Collection, (Status, String)> {
+        use crate::db::schema::collections::dsl::*;
+        self.run(move |connection| collections.filter(id.eq(c_id)).first(connection))
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => (Status::NotFound, "Collection not found".into()),
+                e => internal_error(e),
+            })
+    }
+
+    /// Count the bookmarks in each of the given collections.
+    pub(crate) async fn collection_sizes(
+        &self,
+        c_ids: Vec<i32>,
+    ) -> Result<HashMap<i32, i64>, (Status, String)> {
+        use crate::db::schema::bookmarks::dsl::*;
+        let rows: Vec<i32> = self
+            .run(move |connection| {
+                bookmarks
+                    .filter(collection.eq_any(c_ids))
+                    .select(collection)
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut sizes: HashMap<i32, i64> = HashMap::new();
+        for c_id in rows {
+            *sizes.entry(c_id).or_default() += 1;
+        }
+        Ok(sizes)
+    }
+
+    /// Create a collection and return its id.
+    pub(crate) async fn new_collection(
+        &self,
+        new_collection: NewCollection,
+    ) -> Result<i32, (Status, String)> {
+        use crate::db::schema::collections::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                insert_into(collections)
+                    .values(&new_collection)
+                    .execute(connection)?;
+                collections.select(id).order_by(id.desc()).first(connection)
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Change the name, description and visibility of a collection of the user.
+    pub(crate) async fn update_collection(
+        &self,
+        u_id: i32,
+        c_id: i32,
+        new_name: String,
+        new_description: String,
+        is_public: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::collections::dsl::*;
+        let updated = self
+            .run(move |connection| {
+                update(collections.filter(id.eq(c_id).and(user.eq(u_id))))
+                    .set((
+                        name.eq(new_name),
+                        description.eq(new_description),
+                        public.eq(is_public),
+                    ))
+                    .execute(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+        match updated {
+            0 => Err((Status::NotFound, "Collection not found".into())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete a collection of the user together with its bookmarks.
+    pub(crate) async fn delete_collection(
+        &self,
+        u_id: i32,
+        c_id: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::collections::dsl::*;
+        self.run(move |connection| {
+            delete(collections.filter(id.eq(c_id).and(user.eq(u_id)))).execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Return the bookmarks of a collection in their order.
+    pub(crate) async fn bookmarks(&self, c_id: i32) -> Result<Vec<Bookmark>, (Status, String)> {
+        use crate::db::schema::bookmarks::dsl::*;
+        use crate::db::schema::questions::dsl::{questions, title};
+        self.run(move |connection| {
+            bookmarks
+                .inner_join(questions)
+                .filter(collection.eq(c_id))
+                .order_by(position.asc())
+                .select((id, question, title, answer, position, created))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Add a question, or one of its answers, to the end of a collection of the user.
+    /// Bookmarking the same thing twice has no effect.
+    pub(crate) async fn add_bookmark(
+        &self,
+        u_id: i32,
+        c_id: i32,
+        q_id: i32,
+        a_id: Option<i32>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::answers::dsl::{answers, id as answer_id, question as answered};
+        use crate::db::schema::bookmarks::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                owned_collection(connection, u_id, c_id)?;
+                if let Some(a_id) = a_id {
+                    answers
+                        .filter(answer_id.eq(a_id).and(answered.eq(q_id)))
+                        .select(answer_id)
+                        .first::<i32>(connection)?;
+                }
+                let existing: i64 = bookmarks
+                    .filter(collection.eq(c_id).and(question.eq(q_id)))
+                    .filter(answer.eq(a_id).or(answer.is_null().and(a_id.is_none())))
+                    .select(count_star())
+                    .first(connection)?;
+                if existing > 0 {
+                    return Ok(());
+                }
+                let last: Option<i32> = bookmarks
+                    .filter(collection.eq(c_id))
+                    .select(diesel::dsl::max(position))
+                    .first(connection)?;
+                insert_into(bookmarks)
+                    .values((
+                        collection.eq(c_id),
+                        question.eq(q_id),
+                        answer.eq(a_id),
+                        position.eq(last.unwrap_or(0) + 1),
+                    ))
+                    .execute(connection)?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (Status::NotFound, "Collection or answer not found".into()),
+            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                (Status::BadRequest, "Invalid question id supplied".into())
+            }
+            e => internal_error(e),
+        })
+    }
+
+    /// Remove a bookmark from a collection of the user.
+    pub(crate) async fn remove_bookmark(
+        &self,
+        u_id: i32,
+        c_id: i32,
+        b_id: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::bookmarks::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                owned_collection(connection, u_id, c_id)?;
+                delete(bookmarks.filter(id.eq(b_id).and(collection.eq(c_id)))).execute(connection)
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (Status::NotFound, "Collection not found".into()),
+            e => internal_error(e),
+        })?;
+        Ok(())
+    }
+
+    /// Move a bookmark one place up or down within a collection of the user by swapping it with its neighbour.
+    pub(crate) async fn move_bookmark(
+        &self,
+        u_id: i32,
+        c_id: i32,
+        b_id: i32,
+        up: bool,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::bookmarks::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                owned_collection(connection, u_id, c_id)?;
+                let current: i32 = bookmarks
+                    .filter(id.eq(b_id).and(collection.eq(c_id)))
+                    .select(position)
+                    .first(connection)?;
+                let in_collection = bookmarks.filter(collection.eq(c_id));
+                let neighbour: Option<(i32, i32)> = if up {
+                    in_collection
+                        .filter(position.lt(current))
+                        .order_by(position.desc())
+                        .select((id, position))
+                        .first(connection)
+                        .optional()?
+                } else {
+                    in_collection
+                        .filter(position.gt(current))
+                        .order_by(position.asc())
+                        .select((id, position))
+                        .first(connection)
+                        .optional()?
+                };
+                if let Some((other, other_position)) = neighbour {
+                    update(bookmarks.filter(id.eq(other)))
+                        .set(position.eq(current))
+                        .execute(connection)?;
+                    update(bookmarks.filter(id.eq(b_id)))
+                        .set(position.eq(other_position))
+                        .execute(connection)?;
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (Status::NotFound, "Bookmark not found".into()),
+            e => internal_error(e),
+        })
+    }
+
+    /// Return the points balance of a user.
+    pub(crate) async fn points(&self, u_id: i32) -> Result<i64, (Status, String)> {
+        self.run(move |connection| points(connection, u_id))
+            .await
+            .map_err(internal_error)
+    }
+
+    /// Return the open bounty on a question, if there is one.
+    pub(crate) async fn open_bounty(&self, q_id: i32) -> Result<Option<Bounty>, (Status, String)> {
+        use crate::db::schema::bounties::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            bounties
+                .inner_join(users)
+                .filter(question.eq(q_id).and(status.eq(BountyStatus::Open)))
+                .select((
+                    id, question, username, amount, created, expires, status, answer, awarded,
+                ))
+                .first(connection)
+                .optional()
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the open bounties that expired before the given time.
+    pub(crate) async fn expired_bounties(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<Vec<Bounty>, (Status, String)> {
+        use crate::db::schema::bounties::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            bounties
+                .inner_join(users)
+                .filter(status.eq(BountyStatus::Open).and(expires.le(now)))
+                .select((
+                    id, question, username, amount, created, expires, status, answer, awarded,
+                ))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Select the questions with an open bounty, the highest bounties first.
+    pub(crate) async fn featured_questions(
+        &self,
+    ) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+        use crate::db::schema::bounties::dsl::{amount, bounties, expires, question, status};
+        let featured: Vec<i32> = self
+            .run(move |connection| {
+                bounties
+                    .filter(status.eq(BountyStatus::Open))
+                    .order_by((amount.desc(), expires.asc()))
+                    .select(question)
+                    .load(connection)
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut found = self.questions_with_ids(featured.clone()).await?;
+        found.sort_by_key(|q| featured.iter().position(|&f| f == q.id));
+        Ok(found)
+    }
+
+    /// Offer a bounty on a question, the user must have enough points to pay for it.
+    pub(crate) async fn start_bounty(&self, new_bounty: NewBounty) -> Result<(), (Status, String)> {
+        use crate::db::schema::bounties::dsl::bounties;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                if points(connection, new_bounty.user)? < i64::from(new_bounty.amount) {
+                    return Err(Error::RollbackTransaction);
+                }
+                insert_into(bounties)
+                    .values(&new_bounty)
+                    .execute(connection)?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::RollbackTransaction => (
+                Status::BadRequest,
+                "You do not have enough points for this bounty".into(),
+            ),
+            Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => (
+                Status::BadRequest,
+                "This question already has an open bounty".into(),
+            ),
+            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                (Status::BadRequest, "Invalid question id supplied".into())
+            }
+            e => internal_error(e),
+        })
+    }
+
+    /// Close an open bounty, awarding the given points to an answer of its question if one is given.
+    pub(crate) async fn close_bounty(
+        &self,
+        b_id: i32,
+        award: Option<(i32, i32)>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::answers;
+        use crate::db::schema::bounties::dsl::*;
+
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                let (q_id, offerer): (i32, i32) = bounties
+                    .filter(id.eq(b_id).and(status.eq(BountyStatus::Open)))
+                    .select((question, user))
+                    .first(connection)?;
+                let (a_id, points) = match award {
+                    Some(award) => award,
+                    None => {
+                        update(bounties.filter(id.eq(b_id)))
+                            .set(status.eq(BountyStatus::Expired))
+                            .execute(connection)?;
+                        return Ok(());
+                    }
+                };
+                let answerer = answers::table
+                    .filter(answers::id.eq(a_id).and(answers::question.eq(q_id)))
+                    .select(answers::author)
+                    .first(connection)?;
+                update(bounties.filter(id.eq(b_id)))
+                    .set((
+                        status.eq(BountyStatus::Awarded),
+                        answer.eq(a_id),
+                        awarded.eq(points),
+                    ))
+                    .execute(connection)?;
+                notify(
+                    connection,
+                    offerer,
+                    NewNotification {
+                        user: answerer,
+                        kind: NotificationKind::Bounty,
+                        question: q_id,
+                        answer: Some(a_id),
+                    },
+                )
+            })
+        })
+        .await
+        .map_err(|e: Error| match e {
+            Error::NotFound => (
+                Status::NotFound,
+                "No such bounty or answer on this question".into(),
+            ),
+            e => internal_error(e),
+        })
+    }
+
+    /// Load the questions and answers of the users whose posts changed since badges were last awarded,
+    /// together with the tags of the questions they answered.
+    /// Return them with the id of the last change they include, none if nothing changed.
+    pub(crate) async fn activity(&self) -> Result<Option<(Activity, i32)>, (Status, String)> {
+        use crate::db::schema::{answers, badge_candidates, chosen_tags, questions, tags};
+        use diesel::expression::dsl::max;
+        let loaded = self
+            .run(move |connection| {
+                let last_change: Option<i32> = badge_candidates::table
+                    .select(max(badge_candidates::id))
+                    .first(connection)?;
+                let last_change = match last_change {
+                    Some(last_change) => last_change,
+                    None => return Ok(None),
+                };
+                let changed = || {
+                    badge_candidates::table
+                        .filter(badge_candidates::id.le(last_change))
+                        .select(badge_candidates::user)
+                };
+                let asked: Vec<QuestionActivity> = questions::table
+                    .filter(questions::author.eq_any(changed()))
+                    .select((questions::author, questions::score))
+                    .load(connection)?;
+                let answered: Vec<AnswerActivity> = answers::table
+                    .filter(answers::author.eq_any(changed()))
+                    .select((
+                        answers::author,
+                        answers::question,
+                        answers::score,
+                        answers::accepted,
+                    ))
+                    .load(connection)?;
+                let tagged: Vec<(i32, String)> = chosen_tags::table
+                    .inner_join(tags::table)
+                    .filter(
+                        chosen_tags::question.eq_any(
+                            answers::table
+                                .filter(answers::author.eq_any(changed()))
+                                .select(answers::question),
+                        ),
+                    )
+                    .select((chosen_tags::question, tags::name))
+                    .load(connection)?;
+                Ok::<_, Error>(Some((asked, answered, tagged, last_change)))
+            })
+            .await
+            .map_err(internal_error)?;
+
+        Ok(loaded.map(|(asked, answered, tagged, last_change)| {
+            let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+            for (q_id, name) in tagged {
+                tags.entry(q_id).or_default().push(name);
+            }
+            let activity = Activity {
+                questions: asked,
+                answers: answered,
+                tags,
+            };
+            (activity, last_change)
+        }))
+    }
+
+    /// Award badges to users, badges they already earned are skipped.
+    /// The changes up to the given one are forgotten, their users were evaluated.
+    pub(crate) async fn award_badges(
+        &self,
+        earned: Vec<(i32, String)>,
+        last_change: i32,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::awards::dsl::*;
+        use crate::db::schema::badge_candidates::dsl::{badge_candidates, id as change};
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                for (u_id, key) in earned {
+                    insert_or_ignore_into(awards)
+                        .values((user.eq(u_id), badge.eq(key)))
+                        .execute(connection)?;
+                }
+                delete(badge_candidates.filter(change.le(last_change))).execute(connection)?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return all awarded badges, the oldest first.
+    pub(crate) async fn awards(&self) -> Result<Vec<Award>, (Status, String)> {
+        use crate::db::schema::awards::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            awards
+                .inner_join(users)
+                .order_by(awarded.asc())
+                .select((username, badge, awarded))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the badges a user earned, the newest first.
+    pub(crate) async fn awards_of_user(&self, u_id: i32) -> Result<Vec<Award>, (Status, String)> {
+        use crate::db::schema::awards::dsl::*;
+        use crate::db::schema::users::dsl::{username, users};
+        self.run(move |connection| {
+            awards
+                .inner_join(users)
+                .filter(user.eq(u_id))
+                .order_by(awarded.desc())
+                .select((username, badge, awarded))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Add the counted views to the questions.
+    pub(crate) async fn add_views(
+        &self,
+        counted: HashMap<i32, i32>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::questions::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                for (q_id, count) in counted {
+                    update(questions.filter(id.eq(q_id)))
+                        .set(views.eq(views + count))
+                        .execute(connection)?;
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Load all questions together with the times of their answers to compute their heat.
+    pub(crate) async fn heat_inputs(&self) -> Result<Vec<HeatInput>, (Status, String)> {
+        use crate::db::schema::{answers, questions};
+        let (asked, answered) = self
+            .run(move |connection| {
+                let asked: Vec<(i32, NaiveDateTime, i32, i32)> = questions::table
+                    .select((
+                        questions::id,
+                        questions::time,
+                        questions::score,
+                        questions::views,
+                    ))
+                    .load(connection)?;
+                let answered: Vec<(i32, NaiveDateTime)> = answers::table
+                    .select((answers::question, answers::time))
+                    .load(connection)?;
+                Ok::<_, Error>((asked, answered))
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut answer_times: HashMap<i32, Vec<NaiveDateTime>> = HashMap::new();
+        for (q_id, answered_at) in answered {
+            answer_times.entry(q_id).or_default().push(answered_at);
+        }
+        Ok(asked
+            .into_iter()
+            .map(|(id, time, score, views)| HeatInput {
+                answers: answer_times.remove(&id).unwrap_or_default(),
+                id,
+                time,
+                score,
+                views,
+            })
+            .collect())
+    }
+
+    /// Replace the ranking of the hot questions by the given questions and their heat.
+    pub(crate) async fn store_hot_ranking(
+        &self,
+        ranking: Vec<(i32, f64)>,
+    ) -> Result<(), (Status, String)> {
+        use crate::db::schema::hot_questions::dsl::*;
+        self.run(move |connection| {
+            connection.transaction::<_, Error, _>(|| {
+                delete(hot_questions).execute(connection)?;
+                let rows: Vec<_> = ranking
+                    .into_iter()
+                    .map(|(q_id, q_heat)| (question.eq(q_id), heat.eq(q_heat)))
+                    .collect();
+                insert_into(hot_questions)
+                    .values(&rows)
+                    .execute(connection)?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the ids of the hot questions, the hottest first.
+    pub(crate) async fn hot_ranking(&self) -> Result<Vec<i32>, (Status, String)> {
+        use crate::db::schema::hot_questions::dsl::*;
+        self.run(move |connection| {
+            hot_questions
+                .order_by(heat.desc())
+                .select(question)
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the questions linking to the given one or linked from it.
+    pub(crate) async fn linked_questions(
+        &self,
+        q_id: i32,
+    ) -> Result<Vec<QuestionLink>, (Status, String)> {
+        use crate::db::schema::question_links::dsl::{question_links, source, target};
+        use crate::db::schema::questions::dsl::{id, questions, title};
+        self.run(move |connection| {
+            let linked_to: Vec<i32> = question_links
+                .filter(source.eq(q_id))
+                .select(target)
+                .load(connection)?;
+            let linked_from: Vec<i32> = question_links
+                .filter(target.eq(q_id))
+                .select(source)
+                .load(connection)?;
+            questions
+                .filter(id.eq_any(linked_to).or(id.eq_any(linked_from)))
+                .order_by(id.desc())
+                .select((id, title))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the questions most similar to the given one, the most similar first.
+    pub(crate) async fn related_questions(
+        &self,
+        q_id: i32,
+    ) -> Result<Vec<QuestionLink>, (Status, String)> {
+        use crate::db::schema::questions::dsl::{id, questions, title};
+        use crate::db::schema::related_questions::dsl::{
+            question, related, related_questions, similarity,
+        };
+        self.run(move |connection| {
+            let similar: Vec<i32> = related_questions
+                .filter(question.eq(q_id))
+                .order_by(similarity.desc())
+                .limit(RELATED_LIMIT as i64)
+                .select(related)
+                .load(connection)?;
+            let mut found: Vec<QuestionLink> = questions
+                .filter(id.eq_any(&similar))
+                .select((id, title))
+                .load(connection)?;
+            found.sort_by_key(|q| similar.iter().position(|&s| s == q.id));
+            Ok::<_, Error>(found)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Store the links and related questions of the questions waiting for them and return how many were stored.
+    /// The similarities are computed before the transaction, so the database is only locked to store them.
+    /// They are computed only once per question and stored both ways, so a question gains the similar ones asked
+    /// after it, but stored similarities are not updated as later questions change the weights of the terms.
+    pub(crate) async fn store_pending_related(&self) -> Result<usize, (Status, String)> {
+        use crate::db::schema::answers::dsl::{answers, question as answered, text as answer_text};
+        use crate::db::schema::pending_related::dsl::{
+            id as pending_id, pending_related, question,
+        };
+        use crate::db::schema::questions::dsl::{id, questions, text};
+        use crate::db::schema::related_questions::dsl::{
+            question as related_of, related, related_questions, similarity,
+        };
+        self.run(move |connection| {
+            let pending: Vec<i32> = pending_related
+                .order_by(pending_id)
+                .limit(RELATED_BATCH)
+                .select(question)
+                .load(connection)?;
+            if pending.is_empty() {
+                return Ok(0);
+            }
+            let corpus = related_corpus(connection)?;
+            let similar: Vec<(i32, Vec<(i32, f64)>)> = pending
+                .iter()
+                .map(|&q_id| (q_id, crate::related::most_similar(q_id, &corpus)))
+                .collect();
+            let mut texts: Vec<(i32, String)> = questions
+                .filter(id.eq_any(&pending))
+                .select((id, text))
+                .load(connection)?;
+            texts.extend(
+                answers
+                    .filter(answered.eq_any(&pending))
+                    .select((answered, answer_text))
+                    .load::<(i32, String)>(connection)?,
+            );
+
+            connection.transaction::<_, Error, _>(|| {
+                for (q_id, q_text) in &texts {
+                    store_links(connection, *q_id, q_text)?;
+                }
+                for (q_id, most_similar) in similar {
+                    for (other, score) in most_similar {
+                        for (from, to) in [(q_id, other), (other, q_id)] {
+                            insert_or_ignore_into(related_questions)
+                                .values((related_of.eq(from), related.eq(to), similarity.eq(score)))
+                                .execute(connection)?;
+                        }
+                    }
+                }
+                delete(pending_related.filter(question.eq_any(&pending))).execute(connection)?;
+                Ok(pending.len())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Load the title and text of the questions asked after the one with the given id, oldest first.
+    pub(crate) async fn question_texts_after(
+        &self,
+        after: i32,
+    ) -> Result<Vec<QuestionText>, (Status, String)> {
+        use crate::db::schema::questions::dsl::{id, questions, text, title};
+        self.run(move |connection| {
+            questions
+                .filter(id.gt(after))
+                .order_by(id)
+                .select((id, title, text))
+                .load(connection)
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Return the hash of the sanitization policy the stored HTML was rendered with, if it was stored.
+    pub(crate) async fn rendered_policy(&self) -> Result<Option<String>, (Status, String)> {
+        use crate::db::schema::rendered_policy::dsl::{hash, rendered_policy};
+        self.run(move |connection| rendered_policy.select(hash).first(connection).optional())
+            .await
+            .map_err(internal_error)
+    }
+
+    /// Store the hash of the sanitization policy all stored HTML was rendered with.
+    pub(crate) async fn set_rendered_policy(&self, policy: String) -> Result<(), (Status, String)> {
+        use crate::db::schema::rendered_policy::dsl::{hash, id, rendered_policy};
+        self.run(move |connection| {
+            replace_into(rendered_policy)
+                .values((id.eq(1), hash.eq(policy)))
+                .execute(connection)
+        })
+        .await
+        .map_err(internal_error)?;
+        Ok(())
+    }
+
+    /// Render the markdown of the questions following the one with the given id to HTML again,
+    /// at most `RENDER_BATCH` at once, and return the ids of the rendered ones, none once all were rendered.
+    /// If `only_missing` is set, only the ones without HTML are rendered, i.e. the ones written before it was stored.
+    pub(crate) async fn rerender_questions(
+        &self,
+        after: i32,
+        only_missing: bool,
+    ) -> Result<Vec<i32>, (Status, String)> {
+        use crate::db::schema::questions::dsl::{html, id, questions, text};
+        self.run(move |connection| {
+            let mut batch = questions
+                .filter(id.gt(after))
+                .order_by(id)
+                .limit(RENDER_BATCH)
+                .select((id, text))
+                .into_boxed();
+            if only_missing {
+                batch = batch.filter(html.eq(""));
+            }
+            let rendered: Vec<(i32, String)> = batch
+                .load::<(i32, String)>(connection)?
+                .into_iter()
+                .map(|(q_id, md)| Ok((q_id, render_markdown(connection, &md)?)))
+                .collect::<Result<_, Error>>()?;
+            connection.transaction::<_, Error, _>(|| {
+                for (q_id, rendered_html) in &rendered {
+                    update(questions.filter(id.eq(q_id)))
+                        .set(html.eq(rendered_html))
+                        .execute(connection)?;
+                }
+                Ok(rendered.into_iter().map(|(q_id, _)| q_id).collect())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Render the markdown of the answers following the one with the given id to HTML again,
+    /// like `rerender_questions` does for questions.
+    pub(crate) async fn rerender_answers(
+        &self,
+        after: i32,
+        only_missing: bool,
+    ) -> Result<Vec<i32>, (Status, String)> {
+        use crate::db::schema::answers::dsl::{answers, html, id, text};
+        self.run(move |connection| {
+            let mut batch = answers
+                .filter(id.gt(after))
+                .order_by(id)
+                .limit(RENDER_BATCH)
+                .select((id, text))
+                .into_boxed();
+            if only_missing {
+                batch = batch.filter(html.eq(""));
+            }
+            let rendered: Vec<(i32, String)> = batch
+                .load::<(i32, String)>(connection)?
+                .into_iter()
+                .map(|(a_id, md)| Ok((a_id, render_markdown(connection, &md)?)))
+                .collect::<Result<_, Error>>()?;
+            connection.transaction::<_, Error, _>(|| {
+                for (a_id, rendered_html) in &rendered {
+                    update(answers.filter(id.eq(a_id)))
+                        .set(html.eq(rendered_html))
+                        .execute(connection)?;
+                }
+                Ok(rendered.into_iter().map(|(a_id, _)| a_id).collect())
+            })
+        })
+        .await
+        .map_err(internal_error)
+    }
+
+    /// Render markdown to HTML just like questions and answers are rendered, without storing it.
+    pub(crate) async fn render_markdown(&self, md: String) -> Result<String, (Status, String)> {
+        self.run(move |connection| render_markdown(connection, &md))
+            .await
+            .map_err(internal_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::models::NotificationKind;
+    use crate::db::{test_rocket, DbConn};
+    use rocket::http::Status;
+
+    #[rocket::async_test]
+    async fn only_the_asker_accepts_one_answer() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        let password = || "pw123456".to_string();
+        let asker = conn
+            .register("asker".into(), password(), None)
+            .await
+            .unwrap();
+        let first = conn
+            .register("first".into(), password(), None)
+            .await
+            .unwrap();
+        let second = conn
+            .register("second".into(), password(), None)
+            .await
+            .unwrap();
+        let q_id = conn
+            .new_question(asker.id, "Question".into(), "Text".into(), vec![])
+            .await
+            .unwrap();
+        let a_first = conn.new_answer(first.id, q_id, "One".into()).await.unwrap();
+        let a_second = conn
+            .new_answer(second.id, q_id, "Two".into())
+            .await
+            .unwrap();
+        let accepted = || async {
+            let answers = conn.answers(q_id).await.unwrap();
+            answers
+                .into_iter()
+                .filter(|a| a.accepted)
+                .map(|a| a.id)
+                .collect::<Vec<_>>()
+        };
+        let acceptances = |u_id| {
+            let conn = &conn;
+            async move {
+                let notifications = conn.notifications(u_id).await.unwrap();
+                notifications
+                    .iter()
+                    .filter(|n| matches!(n.kind, NotificationKind::Accepted))
+                    .count()
+            }
+        };
+
+        let err = conn.mark_solved(a_first, first.id).await.unwrap_err();
+        assert_eq!(err.0, Status::Forbidden);
+        assert!(accepted().await.is_empty());
+
+        assert!(conn.mark_solved(a_first, asker.id).await.unwrap());
+        assert_eq!(accepted().await, vec![a_first]);
+
+        assert!(conn.mark_solved(a_second, asker.id).await.unwrap());
+        assert_eq!(accepted().await, vec![a_second]);
+
+        assert!(!conn.mark_solved(a_second, asker.id).await.unwrap());
+        assert_eq!(accepted().await, vec![a_second]);
+        assert_eq!(acceptances(first.id).await, 1);
+        assert_eq!(acceptances(second.id).await, 1);
+
+        let err = conn.mark_solved(-1, asker.id).await.unwrap_err();
+        assert_eq!(err.0, Status::BadRequest);
+    }
 }
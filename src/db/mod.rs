@@ -2,8 +2,100 @@ pub(crate) mod actions;
 pub(crate) mod models;
 pub(crate) mod schema;
 
+use rocket::fairing::AdHoc;
 use rocket_sync_db_pools::database;
+use std::ops::Deref;
+use std::sync::Arc;
 
 /// A Wrapper structure for the main database.
 #[database("rust_overflow")]
 pub(crate) struct DbConn(diesel::SqliteConnection);
+
+/// The connection shared by the short periodic background tasks.
+/// It is taken from the pool once, instead of every task holding a connection the requests could use.
+/// Long-running jobs, like computing related questions or rendering all markdown, use connections of their own,
+/// so they don't hold up the others.
+#[derive(Clone)]
+pub(crate) struct TaskConn(Arc<DbConn>);
+
+impl Deref for TaskConn {
+    type Target = DbConn;
+
+    fn deref(&self) -> &DbConn {
+        &self.0
+    }
+}
+
+impl TaskConn {
+    /// A fairing taking the connection of the background tasks from the pool into the managed state.
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("Task connection", |rocket| async {
+            match DbConn::get_one(&rocket).await {
+                Some(conn) => Ok(rocket.manage(TaskConn(Arc::new(conn)))),
+                None => {
+                    error!("Background tasks need a database connection");
+                    Err(rocket)
+                }
+            }
+        })
+    }
+}
+
+/// The configuration of a server on a new database with all migrations run, for tests which need a database.
+/// The migrations run in the order of their versions compared as text, like the diesel CLI runs them.
+#[cfg(test)]
+pub(crate) fn test_figment() -> rocket::figment::Figment {
+    use diesel::connection::SimpleConnection;
+    use diesel::Connection;
+    use rocket::figment::providers::Serialized;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Once;
+
+    static CLEAR: Once = Once::new();
+    static DATABASES: AtomicUsize = AtomicUsize::new(0);
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/target/test_databases");
+    CLEAR.call_once(|| {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).expect("the directory of the databases can be created");
+    });
+    let path = std::path::Path::new(dir)
+        .join(format!("{}.db3", DATABASES.fetch_add(1, Ordering::Relaxed)));
+    let path = path.to_string_lossy().into_owned();
+
+    let connection =
+        diesel::SqliteConnection::establish(&path).expect("the database can be created");
+    let mut migrations: Vec<_> =
+        std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+            .expect("the migrations can be listed")
+            .map(|entry| entry.expect("the migrations can be listed").path())
+            .collect();
+    migrations.sort_by_key(|migration| {
+        let name = migration
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        name.split('_').next().unwrap().to_string()
+    });
+    for migration in migrations {
+        let sql =
+            std::fs::read_to_string(migration.join("up.sql")).expect("the migration can be read");
+        connection
+            .batch_execute(&sql)
+            .unwrap_or_else(|e| panic!("{} fails: {}", migration.display(), e));
+    }
+
+    rocket::Config::figment()
+        .merge(Serialized::global("databases.rust_overflow.url", path))
+        .merge(Serialized::global("log_level", "off"))
+}
+
+/// A server with a connection pool on a new database, see [`test_figment`].
+#[cfg(test)]
+pub(crate) async fn test_rocket() -> rocket::Rocket<rocket::Ignite> {
+    rocket::custom(test_figment())
+        .attach(DbConn::fairing())
+        .ignite()
+        .await
+        .expect("the server can be built")
+}
@@ -1,32 +1,568 @@
-use crate::db::schema::{answers, questions, users};
+use crate::auth::random_token;
+use crate::db::schema::{
+    answers, api_tokens, bounties, collections, identities, notifications, questions, users,
+    webhook_deliveries, webhooks,
+};
+use async_graphql::{Enum, SimpleObject};
 use bcrypt::hash;
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime};
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::Sqlite;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use utoipa::ToSchema;
+
+/// The role of a user, granting additional permissions on the board.
+#[derive(
+    AsExpression,
+    FromSqlRow,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    Enum,
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for Role {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Role {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "user" => Ok(Role::User),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role {}", other).into()),
+        }
+    }
+}
 
 #[derive(Queryable, Debug, Clone)]
 pub(crate) struct User {
     pub(crate) id: i32,
     pub(crate) username: String,
     pub(crate) password: String,
+    pub(crate) role: Role,
 }
 
 /// A logged in user
-#[derive(Queryable, Serialize, Deserialize, Debug, Clone)]
+#[derive(Queryable, Serialize, Deserialize, ToSchema, SimpleObject, Debug, Clone)]
+#[graphql(name = "User")]
 pub(crate) struct Login {
     pub(crate) id: i32,
     pub(crate) username: String,
+    #[serde(default)]
+    pub(crate) role: Role,
 }
 impl From<User> for Login {
     fn from(u: User) -> Self {
         Login {
             id: u.id,
             username: u.username,
+            role: u.role,
         }
     }
 }
 
-/// Represents a Tag in the Database
+/// A permission granted to an API token.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Scope {
+    /// Read private data of the user.
+    Read,
+    /// Ask, answer and accept answers.
+    Write,
+    /// Vote on questions and answers.
+    Vote,
+}
+
+impl Scope {
+    pub(crate) const ALL: [Scope; 3] = [Scope::Read, Scope::Write, Scope::Vote];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+            Scope::Vote => "vote",
+        }
+    }
+
+    /// Parse a space separated list of scopes, ignoring unknown ones.
+    pub(crate) fn parse_list(scopes: &str) -> Vec<Scope> {
+        Scope::ALL
+            .iter()
+            .copied()
+            .filter(|s| scopes.split_whitespace().any(|name| name == s.as_str()))
+            .collect()
+    }
+}
+
+/// Represents an API token of a user in the Database
+/// Its hash is never read again after creation.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct ApiToken {
+    pub(crate) id: i32,
+    pub(crate) name: String,
+    pub(crate) scopes: String,
+    pub(crate) created: NaiveDateTime,
+    pub(crate) last_used: Option<NaiveDateTime>,
+}
+
+/// The event a notification informs about.
+#[derive(AsExpression, FromSqlRow, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum NotificationKind {
+    /// A question of the user was answered.
+    Answer,
+    /// An answer of the user was accepted.
+    Accepted,
+    /// A question or answer of the user was voted on.
+    Vote,
+    /// A question was asked in a tag the user watches.
+    Question,
+    /// A bounty was awarded to an answer of the user.
+    Bounty,
+}
+
+impl NotificationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Answer => "answer",
+            NotificationKind::Accepted => "accepted",
+            NotificationKind::Vote => "vote",
+            NotificationKind::Question => "question",
+            NotificationKind::Bounty => "bounty",
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for NotificationKind {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for NotificationKind {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "answer" => Ok(NotificationKind::Answer),
+            "accepted" => Ok(NotificationKind::Accepted),
+            "vote" => Ok(NotificationKind::Vote),
+            "question" => Ok(NotificationKind::Question),
+            "bounty" => Ok(NotificationKind::Bounty),
+            other => Err(format!("Unknown notification kind {}", other).into()),
+        }
+    }
+}
+
+/// Represents a Notification in the Database
+/// The title of the question it concerns is added for display.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Notification {
+    pub(crate) id: i32,
+    pub(crate) kind: NotificationKind,
+    pub(crate) question: i32,
+    pub(crate) title: String,
+    pub(crate) answer: Option<i32>,
+    pub(crate) created: NaiveDateTime,
+    pub(crate) read: bool,
+}
+
+/// The kinds of notifications a user wants to receive.
+#[derive(Queryable, Serialize, Debug, Clone, Copy)]
+pub(crate) struct NotificationSettings {
+    pub(crate) answers: bool,
+    pub(crate) accepted: bool,
+    pub(crate) votes: bool,
+    pub(crate) questions: bool,
+}
+
+impl NotificationSettings {
+    /// Return whether notifications of the given kind are wanted.
+    pub(crate) fn wants(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::Answer => self.answers,
+            NotificationKind::Accepted | NotificationKind::Bounty => self.accepted,
+            NotificationKind::Vote => self.votes,
+            NotificationKind::Question => self.questions,
+        }
+    }
+}
+
+/// How often a user receives a digest of new questions in their followed tags.
+#[derive(
+    AsExpression, FromSqlRow, FromFormField, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Digest {
+    #[default]
+    Never,
+    Daily,
+    Weekly,
+}
+
+impl Digest {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Digest::Never => "never",
+            Digest::Daily => "daily",
+            Digest::Weekly => "weekly",
+        }
+    }
+
+    /// The time between two digests, `None` if no digests are sent.
+    pub(crate) fn period(&self) -> Option<Duration> {
+        match self {
+            Digest::Never => None,
+            Digest::Daily => Some(Duration::days(1)),
+            Digest::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for Digest {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Digest {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "never" => Ok(Digest::Never),
+            "daily" => Ok(Digest::Daily),
+            "weekly" => Ok(Digest::Weekly),
+            other => Err(format!("Unknown digest {}", other).into()),
+        }
+    }
+}
+
+/// The emails a user wants to receive.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct EmailSettings {
+    pub(crate) email: Option<String>,
+    pub(crate) verified: bool,
+    /// Whether to send an email for each answer to a followed question.
+    pub(crate) answers: bool,
+    pub(crate) digest: Digest,
+}
+
+/// A user receiving emails, only users with a verified address are subscribers.
+#[derive(Queryable, Debug, Clone)]
+pub(crate) struct Subscriber {
+    pub(crate) id: i32,
+    pub(crate) username: String,
+    pub(crate) email: Option<String>,
+    pub(crate) unsubscribe_token: String,
+    pub(crate) digest: Digest,
+    pub(crate) last_digest: Option<NaiveDateTime>,
+}
+
+/// The board events a webhook can be fired on.
+#[derive(AsExpression, FromSqlRow, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sql_type = "Text"]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WebhookEvent {
+    /// A new question was asked.
+    QuestionCreated,
+    /// A new answer was given.
+    AnswerCreated,
+    /// An answer was accepted by the asker.
+    AnswerAccepted,
+}
+
+impl WebhookEvent {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::QuestionCreated => "question_created",
+            WebhookEvent::AnswerCreated => "answer_created",
+            WebhookEvent::AnswerAccepted => "answer_accepted",
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for WebhookEvent {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for WebhookEvent {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "question_created" => Ok(WebhookEvent::QuestionCreated),
+            "answer_created" => Ok(WebhookEvent::AnswerCreated),
+            "answer_accepted" => Ok(WebhookEvent::AnswerAccepted),
+            other => Err(format!("Unknown webhook event {}", other).into()),
+        }
+    }
+}
+
+/// The state of a webhook delivery in the queue.
+#[derive(AsExpression, FromSqlRow, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DeliveryStatus {
+    /// The delivery waits for its next attempt.
+    Pending,
+    /// The receiver answered with a success status.
+    Delivered,
+    /// All attempts failed, the delivery is not retried anymore.
+    Failed,
+}
+
+impl DeliveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for DeliveryStatus {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for DeliveryStatus {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            other => Err(format!("Unknown delivery status {}", other).into()),
+        }
+    }
+}
+
+/// Represents a Webhook in the Database
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Webhook {
+    pub(crate) id: i32,
+    pub(crate) url: String,
+    #[serde(skip)]
+    pub(crate) secret: String,
+    pub(crate) on_question: bool,
+    pub(crate) on_answer: bool,
+    pub(crate) on_accepted: bool,
+    pub(crate) active: bool,
+    pub(crate) created: NaiveDateTime,
+}
+
+impl Webhook {
+    /// Return whether the webhook is fired on the given event.
+    pub(crate) fn fires_on(&self, event: WebhookEvent) -> bool {
+        self.active
+            && match event {
+                WebhookEvent::QuestionCreated => self.on_question,
+                WebhookEvent::AnswerCreated => self.on_answer,
+                WebhookEvent::AnswerAccepted => self.on_accepted,
+            }
+    }
+}
+
+/// Represents a Delivery of a webhook in the Database
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Delivery {
+    pub(crate) id: i32,
+    pub(crate) webhook: i32,
+    pub(crate) event: WebhookEvent,
+    pub(crate) payload: String,
+    pub(crate) status: DeliveryStatus,
+    pub(crate) attempts: i32,
+    pub(crate) next_attempt: NaiveDateTime,
+    /// The HTTP status of the last response, if the receiver answered at all.
+    pub(crate) response: Option<i32>,
+    pub(crate) error: Option<String>,
+    pub(crate) created: NaiveDateTime,
+}
+
+/// Represents a Collection of bookmarks in the Database
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Collection {
+    pub(crate) id: i32,
+    pub(crate) user: i32,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) public: bool,
+    pub(crate) created: NaiveDateTime,
+}
+
+/// Represents a Bookmark in the Database
+/// The title of the bookmarked question is added for display.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Bookmark {
+    pub(crate) id: i32,
+    pub(crate) question: i32,
+    pub(crate) title: String,
+    /// The bookmarked answer, if not the question itself was bookmarked.
+    pub(crate) answer: Option<i32>,
+    pub(crate) position: i32,
+    pub(crate) created: NaiveDateTime,
+}
+
+/// The state of a bounty.
+#[derive(AsExpression, FromSqlRow, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[sql_type = "Text"]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BountyStatus {
+    /// The bounty runs until it expires or is awarded.
+    Open,
+    /// The bounty went to an answer.
+    Awarded,
+    /// The bounty expired without an answer to award it to.
+    Expired,
+}
+
+impl BountyStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BountyStatus::Open => "open",
+            BountyStatus::Awarded => "awarded",
+            BountyStatus::Expired => "expired",
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for BountyStatus {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        <str as ToSql<Text, Sqlite>>::to_sql(self.as_str(), out)
+    }
+}
+
+impl FromSql<Text, Sqlite> for BountyStatus {
+    fn from_sql(
+        bytes: Option<&<Sqlite as diesel::backend::Backend>::RawValue>,
+    ) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Sqlite>>::from_sql(bytes)?.as_str() {
+            "open" => Ok(BountyStatus::Open),
+            "awarded" => Ok(BountyStatus::Awarded),
+            "expired" => Ok(BountyStatus::Expired),
+            other => Err(format!("Unknown bounty status {}", other).into()),
+        }
+    }
+}
+
+/// Represents a Bounty in the Database
+/// The user offering it is replaced with their username.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Bounty {
+    pub(crate) id: i32,
+    pub(crate) question: i32,
+    pub(crate) offerer: String,
+    pub(crate) amount: i32,
+    pub(crate) created: NaiveDateTime,
+    pub(crate) expires: NaiveDateTime,
+    pub(crate) status: BountyStatus,
+    /// The answer the bounty was awarded to.
+    pub(crate) answer: Option<i32>,
+    /// The points the answer received, half the amount if it was awarded automatically.
+    pub(crate) awarded: Option<i32>,
+}
+
+/// Represents an Award of a badge in the Database
+/// The user is replaced with their username.
 #[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct Award {
+    pub(crate) username: String,
+    pub(crate) badge: String,
+    pub(crate) awarded: NaiveDateTime,
+}
+
+/// A question reduced to what badges are awarded for.
+#[derive(Queryable, Debug, Clone)]
+pub(crate) struct QuestionActivity {
+    pub(crate) author: i32,
+    pub(crate) score: i32,
+}
+
+/// An answer reduced to what badges are awarded for.
+#[derive(Queryable, Debug, Clone)]
+pub(crate) struct AnswerActivity {
+    pub(crate) author: i32,
+    pub(crate) question: i32,
+    pub(crate) score: i32,
+    pub(crate) accepted: bool,
+}
+
+/// The posts of the users whose badges are evaluated, from which badges are awarded.
+#[derive(Debug, Clone)]
+pub(crate) struct Activity {
+    pub(crate) questions: Vec<QuestionActivity>,
+    pub(crate) answers: Vec<AnswerActivity>,
+    /// The names of the tags of each question.
+    pub(crate) tags: HashMap<i32, Vec<String>>,
+}
+
+/// A question reduced to what its heat is computed from.
+#[derive(Debug, Clone)]
+pub(crate) struct HeatInput {
+    pub(crate) id: i32,
+    pub(crate) time: NaiveDateTime,
+    pub(crate) score: i32,
+    pub(crate) views: i32,
+    /// When each answer was given.
+    pub(crate) answers: Vec<NaiveDateTime>,
+}
+
+/// A question as listed in the sidebar of another one.
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub(crate) struct QuestionLink {
+    pub(crate) id: i32,
+    pub(crate) title: String,
+}
+
+/// The words of a question, as searched for duplicates.
+#[derive(Queryable, Debug, Clone)]
+pub(crate) struct QuestionText {
+    pub(crate) id: i32,
+    pub(crate) title: String,
+    pub(crate) text: String,
+}
+
+/// Represents a Tag in the Database
+#[derive(Queryable, Serialize, ToSchema, SimpleObject, Debug, Clone)]
 pub(crate) struct Tag {
     pub(crate) id: i32,
     pub(crate) name: String,
@@ -43,34 +579,51 @@ pub(crate) struct Question {
     pub(crate) score: i32,
     pub(crate) title: String,
     pub(crate) text: String,
+    /// The text rendered to HTML.
+    pub(crate) html: String,
 }
 
 /// A collection of data concerning a question.
 /// Suitable to generate HTMl for a question
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, ToSchema, SimpleObject, Debug, Clone)]
+#[graphql(name = "Question", complex)]
 pub(crate) struct DisplayQuestion {
     pub(crate) id: i32,
+    #[graphql(skip)]
     pub(crate) author: String,
     pub(crate) time: NaiveDateTime,
     pub(crate) score: i32,
     pub(crate) title: String,
     pub(crate) text: String,
+    /// The text rendered to HTML.
+    pub(crate) html: String,
     pub(crate) tags: Vec<Tag>,
     pub(crate) num_answers: i64,
     pub(crate) answered: bool,
+    /// How often the question was bookmarked into collections.
+    pub(crate) bookmarks: i64,
+    /// The amount of the open bounty on the question.
+    pub(crate) bounty: Option<i32>,
+    /// How often the thread was viewed, crawlers and repeated views excluded.
+    pub(crate) views: i32,
 }
 
 /// Represents an Answer in the Database
 /// The author is replaced with the username of the author
-#[derive(Queryable, Serialize, Debug, Clone)]
+#[derive(Queryable, Serialize, ToSchema, SimpleObject, Debug, Clone)]
+#[graphql(complex)]
 pub(crate) struct Answer {
     pub(crate) id: i32,
+    #[graphql(skip)]
     pub(crate) author: String,
+    #[graphql(skip)]
     pub(crate) question: i32,
     pub(crate) time: NaiveDateTime,
     pub(crate) score: i32,
     pub(crate) accepted: bool,
     pub(crate) text: String,
+    /// The text rendered to HTML.
+    pub(crate) html: String,
 }
 
 /// Represents the data needed to create a new User
@@ -80,11 +633,21 @@ pub(crate) struct Answer {
 pub(crate) struct NewUser {
     pub(crate) username: String,
     pub(crate) password: String,
+    pub(crate) email: Option<String>,
+    pub(crate) verified: bool,
+    pub(crate) verification_token: Option<String>,
+    pub(crate) unsubscribe_token: String,
 }
 
 impl NewUser {
     /// Creates a new user including hashing the password
-    pub(crate) fn create(username: String, password: String) -> Result<Self, String> {
+    /// If an email address is given, the user is unverified until it is verified with the generated token.
+    /// Users without an address have nothing to verify.
+    pub(crate) fn create(
+        username: String,
+        password: String,
+        email: Option<String>,
+    ) -> Result<Self, String> {
         // Hash the password using bcrypt
         let hash = hash(password, 12).map_err(|_| "Invalid Password".to_string())?;
 
@@ -92,6 +655,10 @@ impl NewUser {
         Ok(NewUser {
             username,
             password: hash,
+            verification_token: email.as_ref().map(|_| random_token(32)),
+            verified: email.is_none(),
+            email,
+            unsubscribe_token: random_token(32),
         })
     }
 }
@@ -104,6 +671,7 @@ pub(crate) struct NewQuestion {
     pub(crate) author: i32,
     pub(crate) title: String,
     pub(crate) text: String,
+    pub(crate) html: String,
 }
 
 /// Represents the data needed to create a new Answer
@@ -114,4 +682,81 @@ pub(crate) struct NewAnswer {
     pub(crate) author: i32,
     pub(crate) question: i32,
     pub(crate) text: String,
+    pub(crate) html: String,
+}
+
+/// Represents the data needed to link an external identity to a User
+/// I.e. it omits all fields of the `identities` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "identities"]
+pub(crate) struct NewIdentity {
+    pub(crate) user: i32,
+    pub(crate) issuer: String,
+    pub(crate) subject: String,
+}
+
+/// Represents the data needed to create a new API token
+/// I.e. it omits all fields of the `api_tokens` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "api_tokens"]
+pub(crate) struct NewApiToken {
+    pub(crate) user: i32,
+    pub(crate) name: String,
+    pub(crate) hash: String,
+    pub(crate) scopes: String,
+}
+
+/// Represents the data needed to create a new Notification
+/// I.e. it omits all fields of the `notifications` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "notifications"]
+pub(crate) struct NewNotification {
+    pub(crate) user: i32,
+    pub(crate) kind: NotificationKind,
+    pub(crate) question: i32,
+    pub(crate) answer: Option<i32>,
+}
+
+/// Represents the data needed to create a new Webhook
+/// I.e. it omits all fields of the `webhooks` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "webhooks"]
+pub(crate) struct NewWebhook {
+    pub(crate) url: String,
+    pub(crate) secret: String,
+    pub(crate) on_question: bool,
+    pub(crate) on_answer: bool,
+    pub(crate) on_accepted: bool,
+}
+
+/// Represents the data needed to enqueue a new Delivery
+/// I.e. it omits all fields of the `webhook_deliveries` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "webhook_deliveries"]
+pub(crate) struct NewDelivery {
+    pub(crate) webhook: i32,
+    pub(crate) event: WebhookEvent,
+    pub(crate) payload: String,
+}
+
+/// Represents the data needed to create a new Collection
+/// I.e. it omits all fields of the `collections` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "collections"]
+pub(crate) struct NewCollection {
+    pub(crate) user: i32,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) public: bool,
+}
+
+/// Represents the data needed to start a new Bounty
+/// I.e. it omits all fields of the `bounties` table that are filled in with defaults.
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "bounties"]
+pub(crate) struct NewBounty {
+    pub(crate) question: i32,
+    pub(crate) user: i32,
+    pub(crate) amount: i32,
+    pub(crate) expires: NaiveDateTime,
 }
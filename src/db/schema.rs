@@ -1,3 +1,35 @@
+table! {
+    /// Representation of the `answer_votes` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    answer_votes (id) {
+        /// The `id` column of the `answer_votes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `answer_votes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `answer` column of the `answer_votes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        answer -> Integer,
+        /// The `value` column of the `answer_votes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        value -> Integer,
+    }
+}
+
 table! {
     /// Representation of the `answers` table.
     ///
@@ -8,69 +40,623 @@ table! {
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        id -> Integer,
-        /// The `author` column of the `answers` table.
+        id -> Integer,
+        /// The `author` column of the `answers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        author -> Integer,
+        /// The `question` column of the `answers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `time` column of the `answers` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        time -> Timestamp,
+        /// The `score` column of the `answers` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        score -> Integer,
+        /// The `accepted` column of the `answers` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        accepted -> Bool,
+        /// The `text` column of the `answers` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        text -> Text,
+        /// The `html` column of the `answers` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        html -> Text,
+    }
+}
+
+table! {
+    /// Representation of the `api_tokens` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    api_tokens (id) {
+        /// The `id` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `name` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `hash` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        hash -> Text,
+        /// The `scopes` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        scopes -> Text,
+        /// The `created` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+        /// The `last_used` column of the `api_tokens` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_used -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    /// Representation of the `awards` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    awards (id) {
+        /// The `id` column of the `awards` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `awards` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `badge` column of the `awards` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        badge -> Text,
+        /// The `awarded` column of the `awards` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        awarded -> Timestamp,
+    }
+}
+
+table! {
+    /// Representation of the `badge_candidates` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    badge_candidates (id) {
+        /// The `id` column of the `badge_candidates` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `badge_candidates` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `bookmarks` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    bookmarks (id) {
+        /// The `id` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `collection` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        collection -> Integer,
+        /// The `question` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `answer` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        answer -> Nullable<Integer>,
+        /// The `position` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        position -> Integer,
+        /// The `created` column of the `bookmarks` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+    }
+}
+
+table! {
+    /// Representation of the `bounties` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    bounties (id) {
+        /// The `id` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `question` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `user` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `amount` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        amount -> Integer,
+        /// The `created` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+        /// The `expires` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        expires -> Timestamp,
+        /// The `status` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        status -> Text,
+        /// The `answer` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        answer -> Nullable<Integer>,
+        /// The `awarded` column of the `bounties` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        awarded -> Nullable<Integer>,
+    }
+}
+
+table! {
+    /// Representation of the `chosen_tags` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    chosen_tags (id) {
+        /// The `id` column of the `chosen_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `question` column of the `chosen_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `tag` column of the `chosen_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tag -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `collections` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    collections (id) {
+        /// The `id` column of the `collections` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `collections` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `name` column of the `collections` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        name -> Text,
+        /// The `description` column of the `collections` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        description -> Text,
+        /// The `public` column of the `collections` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        public -> Bool,
+        /// The `created` column of the `collections` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+    }
+}
+
+table! {
+    /// Representation of the `followed_questions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    followed_questions (id) {
+        /// The `id` column of the `followed_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `followed_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `question` column of the `followed_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `followed_tags` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    followed_tags (id) {
+        /// The `id` column of the `followed_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `followed_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `tag` column of the `followed_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tag -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `followed_users` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    followed_users (id) {
+        /// The `id` column of the `followed_users` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `followed_users` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `followee` column of the `followed_users` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        followee -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `hot_questions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    hot_questions (id) {
+        /// The `id` column of the `hot_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `question` column of the `hot_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `heat` column of the `hot_questions` table.
+        ///
+        /// Its SQL type is `Double`.
+        ///
+        /// (Automatically generated by Diesel.)
+        heat -> Double,
+        /// The `computed` column of the `hot_questions` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        computed -> Timestamp,
+    }
+}
+
+table! {
+    /// Representation of the `identities` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    identities (id) {
+        /// The `id` column of the `identities` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `identities` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `issuer` column of the `identities` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        issuer -> Text,
+        /// The `subject` column of the `identities` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        subject -> Text,
+    }
+}
+
+table! {
+    /// Representation of the `ignored_tags` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    ignored_tags (id) {
+        /// The `id` column of the `ignored_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `ignored_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `tag` column of the `ignored_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tag -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `notifications` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    notifications (id) {
+        /// The `id` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `user` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `kind` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        kind -> Text,
+        /// The `question` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `answer` column of the `notifications` table.
         ///
-        /// Its SQL type is `Integer`.
+        /// Its SQL type is `Nullable<Integer>`.
         ///
         /// (Automatically generated by Diesel.)
-        author -> Integer,
-        /// The `question` column of the `answers` table.
+        answer -> Nullable<Integer>,
+        /// The `created` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+        /// The `read` column of the `notifications` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        read -> Bool,
+    }
+}
+
+table! {
+    /// Representation of the `pending_related` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    pending_related (id) {
+        /// The `id` column of the `pending_related` table.
         ///
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        question -> Integer,
-        /// The `time` column of the `answers` table.
+        id -> Integer,
+        /// The `question` column of the `pending_related` table.
         ///
-        /// Its SQL type is `Timestamp`.
+        /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        time -> Timestamp,
-        /// The `score` column of the `answers` table.
+        question -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `question_links` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    question_links (id) {
+        /// The `id` column of the `question_links` table.
         ///
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        score -> Integer,
-        /// The `accepted` column of the `answers` table.
+        id -> Integer,
+        /// The `source` column of the `question_links` table.
         ///
-        /// Its SQL type is `Bool`.
+        /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        accepted -> Bool,
-        /// The `text` column of the `answers` table.
+        source -> Integer,
+        /// The `target` column of the `question_links` table.
         ///
-        /// Its SQL type is `Text`.
+        /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        text -> Text,
+        target -> Integer,
     }
 }
 
 table! {
-    /// Representation of the `chosen_tags` table.
+    /// Representation of the `question_votes` table.
     ///
     /// (Automatically generated by Diesel.)
-    chosen_tags (id) {
-        /// The `id` column of the `chosen_tags` table.
+    question_votes (id) {
+        /// The `id` column of the `question_votes` table.
         ///
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
         id -> Integer,
-        /// The `question` column of the `chosen_tags` table.
+        /// The `user` column of the `question_votes` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        user -> Integer,
+        /// The `question` column of the `question_votes` table.
         ///
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
         question -> Integer,
-        /// The `tag` column of the `chosen_tags` table.
+        /// The `value` column of the `question_votes` table.
         ///
         /// Its SQL type is `Integer`.
         ///
         /// (Automatically generated by Diesel.)
-        tag -> Integer,
+        value -> Integer,
     }
 }
 
@@ -115,6 +701,70 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         text -> Text,
+        /// The `views` column of the `questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        views -> Integer,
+        /// The `html` column of the `questions` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        html -> Text,
+    }
+}
+
+table! {
+    /// Representation of the `related_questions` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    related_questions (id) {
+        /// The `id` column of the `related_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `question` column of the `related_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        question -> Integer,
+        /// The `related` column of the `related_questions` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        related -> Integer,
+        /// The `similarity` column of the `related_questions` table.
+        ///
+        /// Its SQL type is `Double`.
+        ///
+        /// (Automatically generated by Diesel.)
+        similarity -> Double,
+    }
+}
+
+table! {
+    /// Representation of the `rendered_policy` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    rendered_policy (id) {
+        /// The `id` column of the `rendered_policy` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `hash` column of the `rendered_policy` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        hash -> Text,
     }
 }
 
@@ -167,13 +817,292 @@ table! {
         ///
         /// (Automatically generated by Diesel.)
         password -> Text,
+        /// The `role` column of the `users` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        role -> Text,
+        /// The `email` column of the `users` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        email -> Nullable<Text>,
+        /// The `verified` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        verified -> Bool,
+        /// The `verification_token` column of the `users` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        verification_token -> Nullable<Text>,
+        /// The `notify_answers` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        notify_answers -> Bool,
+        /// The `notify_accepted` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        notify_accepted -> Bool,
+        /// The `notify_votes` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        notify_votes -> Bool,
+        /// The `email_answers` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        email_answers -> Bool,
+        /// The `digest` column of the `users` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        digest -> Text,
+        /// The `last_digest` column of the `users` table.
+        ///
+        /// Its SQL type is `Nullable<Timestamp>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        last_digest -> Nullable<Timestamp>,
+        /// The `unsubscribe_token` column of the `users` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        unsubscribe_token -> Text,
+        /// The `notify_questions` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        notify_questions -> Bool,
+        /// The `hide_ignored` column of the `users` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        hide_ignored -> Bool,
+    }
+}
+
+table! {
+    /// Representation of the `webhook_deliveries` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webhook_deliveries (id) {
+        /// The `id` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `webhook` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        webhook -> Integer,
+        /// The `event` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        event -> Text,
+        /// The `payload` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        payload -> Text,
+        /// The `status` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        status -> Text,
+        /// The `attempts` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        attempts -> Integer,
+        /// The `next_attempt` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        next_attempt -> Timestamp,
+        /// The `response` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Nullable<Integer>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        response -> Nullable<Integer>,
+        /// The `error` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Nullable<Text>`.
+        ///
+        /// (Automatically generated by Diesel.)
+        error -> Nullable<Text>,
+        /// The `created` column of the `webhook_deliveries` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
+    }
+}
+
+table! {
+    /// Representation of the `webhook_tags` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webhook_tags (id) {
+        /// The `id` column of the `webhook_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `webhook` column of the `webhook_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        webhook -> Integer,
+        /// The `tag` column of the `webhook_tags` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        tag -> Integer,
+    }
+}
+
+table! {
+    /// Representation of the `webhooks` table.
+    ///
+    /// (Automatically generated by Diesel.)
+    webhooks (id) {
+        /// The `id` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Integer`.
+        ///
+        /// (Automatically generated by Diesel.)
+        id -> Integer,
+        /// The `url` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        url -> Text,
+        /// The `secret` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Text`.
+        ///
+        /// (Automatically generated by Diesel.)
+        secret -> Text,
+        /// The `on_question` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        on_question -> Bool,
+        /// The `on_answer` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        on_answer -> Bool,
+        /// The `on_accepted` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        on_accepted -> Bool,
+        /// The `active` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Bool`.
+        ///
+        /// (Automatically generated by Diesel.)
+        active -> Bool,
+        /// The `created` column of the `webhooks` table.
+        ///
+        /// Its SQL type is `Timestamp`.
+        ///
+        /// (Automatically generated by Diesel.)
+        created -> Timestamp,
     }
 }
 
 joinable!(answers -> questions (question));
 joinable!(answers -> users (author));
+joinable!(api_tokens -> users (user));
+joinable!(awards -> users (user));
+joinable!(bookmarks -> answers (answer));
+joinable!(bookmarks -> collections (collection));
+joinable!(bookmarks -> questions (question));
+joinable!(bounties -> questions (question));
+joinable!(bounties -> users (user));
 joinable!(chosen_tags -> questions (question));
 joinable!(chosen_tags -> tags (tag));
+joinable!(collections -> users (user));
+joinable!(followed_questions -> questions (question));
+joinable!(followed_questions -> users (user));
+joinable!(followed_tags -> tags (tag));
+joinable!(followed_tags -> users (user));
+joinable!(followed_users -> users (followee));
+joinable!(hot_questions -> questions (question));
+joinable!(identities -> users (user));
+joinable!(ignored_tags -> tags (tag));
+joinable!(ignored_tags -> users (user));
+joinable!(notifications -> answers (answer));
+joinable!(notifications -> questions (question));
+joinable!(notifications -> users (user));
 joinable!(questions -> users (author));
+joinable!(webhook_deliveries -> webhooks (webhook));
+joinable!(webhook_tags -> tags (tag));
+joinable!(webhook_tags -> webhooks (webhook));
 
-allow_tables_to_appear_in_same_query!(answers, chosen_tags, questions, tags, users,);
+allow_tables_to_appear_in_same_query!(
+    answer_votes,
+    answers,
+    api_tokens,
+    awards,
+    badge_candidates,
+    bookmarks,
+    bounties,
+    chosen_tags,
+    collections,
+    followed_questions,
+    followed_tags,
+    followed_users,
+    hot_questions,
+    identities,
+    ignored_tags,
+    notifications,
+    pending_related,
+    question_links,
+    question_votes,
+    questions,
+    related_questions,
+    rendered_policy,
+    tags,
+    users,
+    webhook_deliveries,
+    webhook_tags,
+    webhooks,
+);
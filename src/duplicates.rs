@@ -0,0 +1,155 @@
+use crate::db::models::{Login, QuestionText};
+use crate::db::DbConn;
+use crate::related::terms;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// How many possible duplicates are suggested.
+const DUPLICATE_LIMIT: usize = 5;
+/// Questions scoring less than this are not suggested.
+const MIN_SCORE: f64 = 2.0;
+/// How fast the score of a term saturates with its frequency in a question.
+const K1: f64 = 1.2;
+/// How much the score is normalized by the length of a question.
+const B: f64 = 0.75;
+/// Terms of the title count this many times, titles say what a question is about.
+const TITLE_BOOST: usize = 2;
+
+/// An existing question which may already ask what is being asked.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Duplicate {
+    id: i32,
+    title: String,
+    score: f64,
+}
+
+/// Count the terms of a question, boosting the ones of its title.
+fn term_counts(title: &str, text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for term in terms(title) {
+        *counts.entry(term).or_default() += TITLE_BOOST;
+    }
+    for term in terms(text) {
+        *counts.entry(term).or_default() += 1;
+    }
+    counts
+}
+
+/// A question as searched for duplicates.
+#[derive(Debug)]
+struct Document {
+    id: i32,
+    title: String,
+    counts: HashMap<String, usize>,
+    length: usize,
+}
+
+/// The questions searched for duplicates together with the statistics BM25 needs.
+/// Questions are never edited, so it is only extended by the questions asked since it was last used.
+#[derive(Debug, Default)]
+struct Corpus {
+    /// The id of the newest question in the corpus.
+    newest: i32,
+    documents: Vec<Document>,
+    /// In how many questions each term occurs.
+    frequency: HashMap<String, usize>,
+    /// The number of terms of all questions.
+    total_length: usize,
+}
+
+impl Corpus {
+    fn extend(&mut self, questions: Vec<QuestionText>) {
+        // Another request may have added the questions in the meantime
+        for question in questions {
+            if question.id <= self.newest {
+                continue;
+            }
+            let counts = term_counts(&question.title, &question.text);
+            for term in counts.keys() {
+                *self.frequency.entry(term.clone()).or_default() += 1;
+            }
+            let length = counts.values().sum();
+            self.total_length += length;
+            self.newest = question.id;
+            self.documents.push(Document {
+                id: question.id,
+                title: question.title,
+                counts,
+                length,
+            });
+        }
+    }
+
+    /// Rank the questions by their Okapi BM25 score for the terms of the new question.
+    fn rank(&self, title: &str, text: &str) -> Vec<Duplicate> {
+        if self.documents.is_empty() {
+            return vec![];
+        }
+        let query = term_counts(title, text);
+        let total = self.documents.len() as f64;
+        let average_length = self.total_length as f64 / total;
+        let idf: HashMap<&str, f64> = query
+            .keys()
+            .map(|term| {
+                let df = self.frequency.get(term).copied().unwrap_or_default() as f64;
+                (term.as_str(), ((total - df + 0.5) / (df + 0.5)).ln_1p())
+            })
+            .collect();
+
+        let mut duplicates: Vec<Duplicate> = self
+            .documents
+            .iter()
+            .map(|document| {
+                let norm = K1 * (1.0 - B + B * document.length as f64 / average_length.max(1.0));
+                let score = query
+                    .keys()
+                    .filter_map(|term| {
+                        let tf = *document.counts.get(term)? as f64;
+                        Some(idf[term.as_str()] * tf * (K1 + 1.0) / (tf + norm))
+                    })
+                    .sum();
+                Duplicate {
+                    id: document.id,
+                    title: document.title.clone(),
+                    score,
+                }
+            })
+            .filter(|d| d.score >= MIN_SCORE)
+            .collect();
+        duplicates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        duplicates.truncate(DUPLICATE_LIMIT);
+        duplicates
+    }
+}
+
+/// The corpus shared by all requests, built on the first one.
+static CORPUS: OnceLock<Mutex<Corpus>> = OnceLock::new();
+
+#[derive(Debug, FromForm)]
+pub(crate) struct DuplicatesForm {
+    title: String,
+    question: String,
+}
+
+/// Suggest existing questions the one being asked may duplicate, queried by the ask form while typing.
+#[post("/ask/duplicates", data = "<form>")]
+pub(crate) async fn duplicates(
+    _user: Login,
+    conn: DbConn,
+    form: Form<DuplicatesForm>,
+) -> Result<Json<Vec<Duplicate>>, (Status, String)> {
+    let DuplicatesForm { title, question } = form.into_inner();
+    if terms(&title).is_empty() {
+        return Ok(Json(vec![]));
+    }
+    let corpus = CORPUS.get_or_init(Mutex::default);
+    let newest = corpus.lock().unwrap().newest;
+    let asked = conn.question_texts_after(newest).await?;
+    let mut corpus = corpus.lock().unwrap();
+    corpus.extend(asked);
+    Ok(Json(corpus.rank(&title, &question)))
+}
@@ -0,0 +1,144 @@
+use crate::db::models::{Answer, DisplayQuestion};
+use crate::db::DbConn;
+use crate::frontend;
+use crate::mail::Mail;
+use crate::ranking::Sort;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use rocket::http::{ContentType, Status};
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+
+/// The number of the newest entries a feed contains.
+const FEED_LIMIT: i64 = 50;
+
+/// An Atom feed, rendered by the `feed` template.
+#[derive(Debug, Clone, Serialize)]
+struct FeedCtx {
+    title: String,
+    subtitle: String,
+    /// The page this feed belongs to.
+    link: String,
+    /// The location of the feed itself.
+    feed_link: String,
+    updated: String,
+
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Entry {
+    link: String,
+    title: String,
+    author: String,
+    updated: String,
+    /// The text of the entry rendered to HTML.
+    content: String,
+}
+
+/// Format a timestamp of the database, which are stored in local time, as required by Atom.
+fn timestamp(time: NaiveDateTime) -> String {
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| format!("{}Z", time.format("%Y-%m-%dT%H:%M:%S")))
+}
+
+fn question_entry(mail: &Mail, question: DisplayQuestion) -> Entry {
+    Entry {
+        link: mail.link(&uri!(frontend::thread(id = question.id)).to_string()),
+        title: question.title,
+        author: question.author,
+        updated: timestamp(question.time),
+        content: question.html,
+    }
+}
+
+fn answer_entry(mail: &Mail, question: &DisplayQuestion, answer: Answer) -> Entry {
+    let thread = uri!(frontend::thread(id = question.id)).to_string();
+    Entry {
+        link: mail.link(&format!("{}#answer-{}", thread, answer.id)),
+        title: format!("Answer by {} to {}", answer.author, question.title),
+        author: answer.author,
+        updated: timestamp(answer.time),
+        content: answer.html,
+    }
+}
+
+fn render_feed(ctx: FeedCtx) -> (ContentType, Template) {
+    (
+        ContentType::new("application", "atom+xml"),
+        Template::render("feed", ctx),
+    )
+}
+
+/// A feed of the newest questions on the board.
+#[get("/feed.atom")]
+pub(crate) async fn index(
+    conn: DbConn,
+    mail: &State<Mail>,
+) -> Result<(ContentType, Template), (Status, String)> {
+    let questions = conn.latest_questions(FEED_LIMIT).await?;
+    let updated = questions.iter().map(|q| q.time).max();
+    Ok(render_feed(FeedCtx {
+        title: "New Questions".into(),
+        subtitle: "The latest questions on this board.".into(),
+        link: mail.link(&uri!(frontend::index(_)).to_string()),
+        feed_link: mail.link(&uri!(index).to_string()),
+        updated: timestamp(updated.unwrap_or_else(|| Local::now().naive_local())),
+        entries: questions
+            .into_iter()
+            .map(|q| question_entry(mail, q))
+            .collect(),
+    }))
+}
+
+/// A feed of the newest questions with one of the given tags.
+#[get("/t/<tags>/feed.atom")]
+pub(crate) async fn tagged_question(
+    conn: DbConn,
+    mail: &State<Mail>,
+    tags: String,
+) -> Result<(ContentType, Template), (Status, String)> {
+    let tag_names: Vec<String> = tags.split('+').map(String::from).collect();
+    let selected_tags = conn.tags_with_names(tag_names.clone()).await?;
+    let questions = conn
+        .latest_questions_with_tag(tag_names.clone(), FEED_LIMIT)
+        .await?;
+    let updated = questions.iter().map(|q| q.time).max();
+    Ok(render_feed(FeedCtx {
+        title: tag_names.join(", "),
+        subtitle: selected_tags[0].description.clone(),
+        link: mail.link(&uri!(frontend::tagged_question(tags = &tags, sort = _)).to_string()),
+        feed_link: mail.link(&uri!(tagged_question(tags = &tags)).to_string()),
+        updated: timestamp(updated.unwrap_or_else(|| Local::now().naive_local())),
+        entries: questions
+            .into_iter()
+            .map(|q| question_entry(mail, q))
+            .collect(),
+    }))
+}
+
+/// A feed of the answers to a question, the newest first.
+#[get("/q/<id>/feed.atom")]
+pub(crate) async fn thread(
+    conn: DbConn,
+    mail: &State<Mail>,
+    id: i32,
+) -> Result<(ContentType, Template), (Status, String)> {
+    let question = conn.question(id).await?;
+    let answers = conn.latest_answers(id, FEED_LIMIT).await?;
+    let updated = answers.first().map_or(question.time, |a| a.time);
+    Ok(render_feed(FeedCtx {
+        title: question.title.clone(),
+        subtitle: format!("Answers to the question of {}", question.author),
+        link: mail.link(&uri!(frontend::thread(id = question.id)).to_string()),
+        feed_link: mail.link(&uri!(thread(id = question.id)).to_string()),
+        updated: timestamp(updated),
+        entries: answers
+            .into_iter()
+            .map(|a| answer_entry(mail, &question, a))
+            .collect(),
+    }))
+}
@@ -1,33 +1,111 @@
-use crate::db::models::{Answer, DisplayQuestion, Login, Tag};
+use crate::badges::{self, EarnedBadge};
+use crate::bounties::BountyOffer;
+use crate::db::models::{
+    Answer, Bounty, Collection, DisplayQuestion, Login, QuestionLink, Role, Tag,
+};
 use crate::db::DbConn;
+use crate::feed;
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use crate::ranking::{self, Sort};
+use crate::views::{ViewCounter, Viewer};
 use rocket::http::Status;
+use rocket::State;
 use rocket_dyn_templates::Template;
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
 struct QuestionsCtx {
     user: Option<String>,
+    sso: bool,
+    unread: i64,
+    /// The Atom feed of this page.
+    feed: Option<String>,
 
     title: String,
     description: String,
+    /// The user whose questions are listed.
+    profile: Option<Profile>,
+    /// The order of the questions, if the user can choose it.
+    sort: Option<Sort>,
 
     all_tags: Vec<Tag>,
     selected_tags: Vec<Tag>,
 
     num_questions: usize,
+    questions: Vec<ListedQuestion>,
+}
+
+/// A question in a list, marked according to the tags the current user watches and ignores.
+#[derive(Debug, Clone, Serialize)]
+struct ListedQuestion {
+    #[serde(flatten)]
+    question: DisplayQuestion,
+    watched: bool,
+    ignored: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Profile {
+    username: String,
+    role: Role,
+    num_followers: i64,
+    points: i64,
+    badges: Vec<EarnedBadge>,
+    /// Whether the current user follows this one.
+    following: bool,
+    /// Whether this is the profile of the current user.
+    own: bool,
+}
+
+/// Mark the questions with tags the user watches or ignores.
+/// Questions with ignored tags are left out if the user chose to hide them.
+async fn mark_questions(
+    conn: &DbConn,
+    user: &Option<Login>,
     questions: Vec<DisplayQuestion>,
+) -> Result<Vec<ListedQuestion>, (Status, String)> {
+    let (watched, ignored, hide) = match user {
+        Some(user) => (
+            conn.followed_tags(user.id).await?,
+            conn.ignored_tags(user.id).await?,
+            conn.hides_ignored(user.id).await?,
+        ),
+        None => (vec![], vec![], false),
+    };
+    Ok(questions
+        .into_iter()
+        .map(|question| ListedQuestion {
+            watched: question.tags.iter().any(|t| watched.contains(&t.id)),
+            ignored: question.tags.iter().any(|t| ignored.contains(&t.id)),
+            question,
+        })
+        .filter(|q| !(hide && q.ignored))
+        .collect())
 }
 
-#[get("/")]
-pub(crate) async fn index(user: Option<Login>, conn: DbConn) -> Result<Template, (Status, String)> {
-    let questions = conn.newest_questions().await?;
+#[get("/?<sort>")]
+pub(crate) async fn index(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    sort: Option<Sort>,
+) -> Result<Template, (Status, String)> {
+    let sort = sort.unwrap_or_default();
+    let questions = ranking::questions(&conn, vec![], sort).await?;
+    let questions = mark_questions(&conn, &user, questions).await?;
     Ok(Template::render(
         "questions",
         QuestionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
             user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            feed: Some(uri!(feed::index).to_string()),
 
             title: "New Questions".into(),
             description: "The latest questions on this board.".into(),
+            profile: None,
+            sort: Some(sort),
 
             all_tags: conn.all_tags().await?,
             selected_tags: vec![],
@@ -38,25 +116,141 @@ pub(crate) async fn index(user: Option<Login>, conn: DbConn) -> Result<Template,
     ))
 }
 
-#[get("/t/<tags>")]
+#[get("/t/<tags>?<sort>")]
 pub(crate) async fn tagged_question(
     user: Option<Login>,
     conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
     tags: String,
+    sort: Option<Sort>,
 ) -> Result<Template, (Status, String)> {
+    let sort = sort.unwrap_or_default();
     let tag_names: Vec<String> = tags.split('+').map(String::from).collect();
-    let tags = conn.tags_with_names(tag_names.clone()).await?;
-    let questions = conn.questions_with_tag(tag_names.clone()).await?;
+    let selected_tags = conn.tags_with_names(tag_names.clone()).await?;
+    let questions = ranking::questions(&conn, tag_names.clone(), sort).await?;
+    let questions = mark_questions(&conn, &user, questions).await?;
     Ok(Template::render(
         "questions",
         QuestionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
             user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            feed: Some(uri!(feed::tagged_question(tags = &tags)).to_string()),
 
             title: tag_names.join(", "),
-            description: tags[0].description.clone(),
+            description: selected_tags[0].description.clone(),
+            profile: None,
+            sort: Some(sort),
+
+            all_tags: conn.all_tags().await?,
+            selected_tags,
+
+            num_questions: questions.len(),
+            questions,
+        },
+    ))
+}
+
+/// The questions the user follows, asked by users they follow or tagged with tags they watch.
+#[get("/feed")]
+pub(crate) async fn my_feed(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    let questions = conn.personal_feed(user.id).await?;
+    let user = Some(user);
+    let questions = mark_questions(&conn, &user, questions).await?;
+    Ok(Template::render(
+        "questions",
+        QuestionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            feed: None,
+
+            title: "My Feed".into(),
+            description:
+                "Questions you follow, asked by users you follow or tagged with tags you watch."
+                    .into(),
+            profile: None,
+            sort: None,
+
+            all_tags: conn.all_tags().await?,
+            selected_tags: vec![],
+
+            num_questions: questions.len(),
+            questions,
+        },
+    ))
+}
+
+/// The questions with an open bounty.
+#[get("/featured")]
+pub(crate) async fn featured(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    let questions = mark_questions(&conn, &user, conn.featured_questions().await?).await?;
+    Ok(Template::render(
+        "questions",
+        QuestionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            feed: None,
+
+            title: "Featured".into(),
+            description: "Questions with an open bounty, the highest bounties first.".into(),
+            profile: None,
+            sort: None,
 
             all_tags: conn.all_tags().await?,
-            selected_tags: tags,
+            selected_tags: vec![],
+
+            num_questions: questions.len(),
+            questions,
+        },
+    ))
+}
+
+#[get("/u/<name>")]
+pub(crate) async fn profile(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    name: String,
+) -> Result<Template, (Status, String)> {
+    let shown = conn.user_with_name(name).await?;
+    let following = match &user {
+        Some(user) => conn.follows_user(user.id, shown.id).await?,
+        None => false,
+    };
+    let questions = mark_questions(&conn, &user, conn.questions_of_user(shown.id).await?).await?;
+    Ok(Template::render(
+        "questions",
+        QuestionsCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            sso: oidc.is_some(),
+            feed: None,
+
+            title: shown.username.clone(),
+            description: format!("Questions asked by {}.", shown.username),
+            profile: Some(Profile {
+                own: user.as_ref().map(|u| u.id == shown.id).unwrap_or(false),
+                num_followers: conn.num_followers(shown.id).await?,
+                points: conn.points(shown.id).await?,
+                badges: badges::earned_badges(conn.awards_of_user(shown.id).await?),
+                username: shown.username,
+                role: shown.role,
+                following,
+            }),
+            user: user.map(|u| u.username),
+            sort: None,
+
+            all_tags: conn.all_tags().await?,
+            selected_tags: vec![],
 
             num_questions: questions.len(),
             questions,
@@ -67,7 +261,24 @@ pub(crate) async fn tagged_question(
 #[derive(Debug, Clone, Serialize)]
 struct ThreadCtx {
     user: Option<String>,
+    sso: bool,
+    unread: i64,
+    /// The Atom feed of this page.
+    feed: String,
     owner: bool,
+    following: bool,
+    /// The collections of the user to bookmark the question or its answers into.
+    collections: Vec<Collection>,
+    /// The open bounty on the question.
+    bounty: Option<Bounty>,
+    /// Whether the current user offered the open bounty and may award it.
+    offerer: bool,
+    /// Set if the current user may offer a bounty on the question.
+    offer: Option<BountyOffer>,
+    /// The questions linking to this one or linked from it.
+    linked: Vec<QuestionLink>,
+    /// The questions most similar to this one.
+    related: Vec<QuestionLink>,
 
     question: DisplayQuestion,
 
@@ -75,23 +286,58 @@ struct ThreadCtx {
     answers: Vec<Answer>,
 }
 
+/// A question with its answers, every viewer but crawlers is counted once per hour.
 #[get("/q/<id>")]
 pub(crate) async fn thread(
     user: Option<Login>,
+    viewer: Option<Viewer>,
+    views: &State<ViewCounter>,
     conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
     id: i32,
 ) -> Result<Template, (Status, String)> {
     let question = conn.question(id).await?;
+    if let Some(viewer) = viewer {
+        views.record(id, viewer);
+    }
     let answers = conn.answers(id).await?;
+    let (following, collections) = match &user {
+        Some(user) => (
+            conn.follows_question(user.id, id).await?,
+            conn.collections_of_user(user.id, true).await?,
+        ),
+        None => (false, vec![]),
+    };
     let owner = user
         .as_ref()
         .map(|u| u.username == question.author)
         .unwrap_or(false);
+    let bounty = conn.open_bounty(id).await?;
+    let offerer = match (&user, &bounty) {
+        (Some(user), Some(bounty)) => user.username == bounty.offerer,
+        _ => false,
+    };
+    let offer = match &user {
+        Some(user) if owner && bounty.is_none() && !question.answered => {
+            Some(BountyOffer::new(conn.points(user.id).await?))
+        }
+        _ => None,
+    };
     Ok(Template::render(
         "thread",
         ThreadCtx {
+            unread: notifications::unread(&conn, &user).await?,
             user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            feed: uri!(feed::thread(id = question.id)).to_string(),
             owner,
+            following,
+            collections,
+            bounty,
+            offerer,
+            offer,
+            linked: conn.linked_questions(id).await?,
+            related: conn.related_questions(id).await?,
             question,
             num_answers: answers.len(),
             answers,
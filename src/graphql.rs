@@ -0,0 +1,425 @@
+use crate::api::{require_text, vote_diff, ApiError, ApiUser};
+use crate::backend::RegistrationConfig;
+use crate::db::models::{Answer, DisplayQuestion, Login, Scope, Tag, WebhookEvent};
+use crate::db::DbConn;
+use crate::mail::Mail;
+use crate::subscriptions;
+use crate::webhooks;
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    ComplexObject, Context, EmptySubscription, Error, ErrorExtensions, Object, Result, Schema,
+};
+use rocket::http::Status;
+use rocket::response::content::Html;
+use rocket::serde::json::Json;
+use rocket::State;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The schema served at `/graphql`.
+pub(crate) type BoardSchema = Schema<Query, Mutation, EmptySubscription>;
+
+/// The deepest nesting of a query, the introspection query of GraphiQL needs 13 levels.
+const MAX_DEPTH: usize = 15;
+
+/// The highest complexity of a query, fields count one and lists count their fields this many times.
+const MAX_COMPLEXITY: usize = 1000;
+
+/// The number of items a list is assumed to hold when computing the complexity of a query.
+const LIST_COMPLEXITY: usize = 10;
+
+pub(crate) fn schema() -> BoardSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+impl From<ApiError> for Error {
+    fn from(e: ApiError) -> Self {
+        let status = e.status;
+        Error::new(e.error).extend_with(|_, extensions| extensions.set("status", status))
+    }
+}
+
+fn db_error((status, error): (Status, String)) -> Error {
+    ApiError::from((status, error)).into()
+}
+
+/// Return the user of the request if it is allowed to act within the given scope.
+fn require_user<'c>(ctx: &Context<'c>, scope: Scope) -> Result<&'c Login> {
+    match ctx.data::<Option<ApiUser>>()? {
+        Some(user) => Ok(user.require(scope)?),
+        None => Err(ApiError::new(Status::Unauthorized, "Please log in").into()),
+    }
+}
+
+// Loaders batching the lookups of all objects resolved in one request into single queries.
+
+/// Loads the answers of questions.
+struct AnswerLoader(Arc<DbConn>);
+
+impl Loader<i32> for AnswerLoader {
+    type Value = Vec<Answer>;
+    type Error = Error;
+
+    async fn load(&self, keys: &[i32]) -> Result<HashMap<i32, Vec<Answer>>> {
+        let mut res: HashMap<i32, Vec<Answer>> = HashMap::new();
+        for answer in self
+            .0
+            .answers_of_questions(keys.to_vec())
+            .await
+            .map_err(db_error)?
+        {
+            res.entry(answer.question).or_default().push(answer);
+        }
+        Ok(res)
+    }
+}
+
+/// Loads questions by their id.
+struct QuestionLoader(Arc<DbConn>);
+
+impl Loader<i32> for QuestionLoader {
+    type Value = DisplayQuestion;
+    type Error = Error;
+
+    async fn load(&self, keys: &[i32]) -> Result<HashMap<i32, DisplayQuestion>> {
+        let questions = self
+            .0
+            .questions_with_ids(keys.to_vec())
+            .await
+            .map_err(db_error)?;
+        Ok(questions.into_iter().map(|q| (q.id, q)).collect())
+    }
+}
+
+/// Loads users by their name.
+struct UserLoader(Arc<DbConn>);
+
+impl Loader<String> for UserLoader {
+    type Value = Login;
+    type Error = Error;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Login>> {
+        let users = self
+            .0
+            .users_with_names(keys.to_vec())
+            .await
+            .map_err(db_error)?;
+        Ok(users.into_iter().map(|u| (u.username.clone(), u)).collect())
+    }
+}
+
+#[ComplexObject]
+impl DisplayQuestion {
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<Login>> {
+        ctx.data::<DataLoader<UserLoader>>()?
+            .load_one(self.author.clone())
+            .await
+    }
+
+    /// The answers of this question, the highest voted first.
+    #[graphql(complexity = "LIST_COMPLEXITY * child_complexity")]
+    async fn answers(&self, ctx: &Context<'_>) -> Result<Vec<Answer>> {
+        let answers = ctx
+            .data::<DataLoader<AnswerLoader>>()?
+            .load_one(self.id)
+            .await?;
+        Ok(answers.unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl Answer {
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<Login>> {
+        ctx.data::<DataLoader<UserLoader>>()?
+            .load_one(self.author.clone())
+            .await
+    }
+
+    /// The question this answer belongs to.
+    async fn question(&self, ctx: &Context<'_>) -> Result<Option<DisplayQuestion>> {
+        ctx.data::<DataLoader<QuestionLoader>>()?
+            .load_one(self.question)
+            .await
+    }
+}
+
+pub(crate) struct Query;
+
+#[Object]
+impl Query {
+    /// The newest questions, optionally only those with one of the given tags.
+    #[graphql(complexity = "LIST_COMPLEXITY * child_complexity")]
+    async fn questions(
+        &self,
+        ctx: &Context<'_>,
+        tags: Option<Vec<String>>,
+    ) -> Result<Vec<DisplayQuestion>> {
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        match tags {
+            Some(tags) if !tags.is_empty() => conn.questions_with_tag(tags).await,
+            _ => conn.newest_questions().await,
+        }
+        .map_err(db_error)
+    }
+
+    async fn question(&self, ctx: &Context<'_>, id: i32) -> Result<Option<DisplayQuestion>> {
+        ctx.data::<DataLoader<QuestionLoader>>()?.load_one(id).await
+    }
+
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<Tag>> {
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        conn.all_tags().await.map_err(db_error)
+    }
+
+    async fn user(&self, ctx: &Context<'_>, username: String) -> Result<Option<Login>> {
+        ctx.data::<DataLoader<UserLoader>>()?
+            .load_one(username)
+            .await
+    }
+
+    /// The user the request is authenticated as, requires the `read` scope.
+    async fn me(&self, ctx: &Context<'_>) -> Result<Login> {
+        Ok(require_user(ctx, Scope::Read)?.clone())
+    }
+}
+
+pub(crate) struct Mutation;
+
+#[Object]
+impl Mutation {
+    /// Ask a new question, requires the `write` scope.
+    async fn ask(
+        &self,
+        ctx: &Context<'_>,
+        title: String,
+        text: String,
+        #[graphql(default)] tags: Vec<i32>,
+    ) -> Result<DisplayQuestion> {
+        let user = require_user(ctx, Scope::Write)?;
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        ctx.data::<RegistrationConfig>()?
+            .check_can_post(conn, user)
+            .await
+            .map_err(db_error)?;
+        require_text("title", &title)?;
+        require_text("text", &text)?;
+
+        let new_id = conn
+            .new_question(user.id, title, text, tags)
+            .await
+            .map_err(db_error)?;
+        let mail = ctx.data::<Mail>()?;
+        webhooks::fire(conn, mail, WebhookEvent::QuestionCreated, new_id, None).await;
+        conn.question(new_id).await.map_err(db_error)
+    }
+
+    /// Answer a question, requires the `write` scope.
+    async fn answer(&self, ctx: &Context<'_>, question_id: i32, text: String) -> Result<Answer> {
+        let user = require_user(ctx, Scope::Write)?;
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        ctx.data::<RegistrationConfig>()?
+            .check_can_post(conn, user)
+            .await
+            .map_err(db_error)?;
+        require_text("text", &text)?;
+
+        conn.question(question_id).await.map_err(db_error)?;
+        let new_id = conn
+            .new_answer(user.id, question_id, text)
+            .await
+            .map_err(db_error)?;
+        let mail = ctx.data::<Mail>()?;
+        subscriptions::answer_emails(conn, mail, user, new_id).await;
+        webhooks::fire(
+            conn,
+            mail,
+            WebhookEvent::AnswerCreated,
+            question_id,
+            Some(new_id),
+        )
+        .await;
+        conn.answer(new_id).await.map_err(db_error)
+    }
+
+    /// Vote on a question with `1` or `-1`, requires the `vote` scope.
+    async fn vote_question(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        value: i32,
+    ) -> Result<DisplayQuestion> {
+        let user = require_user(ctx, Scope::Vote)?;
+        let diff = vote_diff(value)?;
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        conn.question(id).await.map_err(db_error)?;
+        conn.update_question_score(id, diff, user.id)
+            .await
+            .map_err(db_error)?;
+        conn.question(id).await.map_err(db_error)
+    }
+
+    /// Vote on an answer with `1` or `-1`, requires the `vote` scope.
+    async fn vote_answer(&self, ctx: &Context<'_>, id: i32, value: i32) -> Result<Answer> {
+        let user = require_user(ctx, Scope::Vote)?;
+        let diff = vote_diff(value)?;
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        conn.answer(id).await.map_err(db_error)?;
+        conn.update_answer_score(id, diff, user.id)
+            .await
+            .map_err(db_error)?;
+        conn.answer(id).await.map_err(db_error)
+    }
+
+    /// Accept an answer, only the author of the question may do so. Requires the `write` scope.
+    async fn accept(&self, ctx: &Context<'_>, answer_id: i32) -> Result<Answer> {
+        let user = require_user(ctx, Scope::Write)?;
+        let conn = ctx.data::<Arc<DbConn>>()?;
+        let answer = conn.answer(answer_id).await.map_err(db_error)?;
+        if conn
+            .mark_solved(answer_id, user.id)
+            .await
+            .map_err(db_error)?
+        {
+            let mail = ctx.data::<Mail>()?;
+            webhooks::fire(
+                conn,
+                mail,
+                WebhookEvent::AnswerAccepted,
+                answer.question,
+                Some(answer_id),
+            )
+            .await;
+        }
+        conn.answer(answer_id).await.map_err(db_error)
+    }
+}
+
+/// Execute a GraphQL request.
+/// Users authenticate like for the JSON API, i.e. with an API token or the login cookie.
+#[post("/graphql", data = "<request>")]
+pub(crate) async fn graphql(
+    schema: &State<BoardSchema>,
+    registration: &State<RegistrationConfig>,
+    mail: &State<Mail>,
+    conn: DbConn,
+    user: Option<ApiUser>,
+    request: Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let conn = Arc::new(conn);
+    let request = request
+        .into_inner()
+        .data(DataLoader::new(
+            AnswerLoader(conn.clone()),
+            rocket::tokio::spawn,
+        ))
+        .data(DataLoader::new(
+            QuestionLoader(conn.clone()),
+            rocket::tokio::spawn,
+        ))
+        .data(DataLoader::new(
+            UserLoader(conn.clone()),
+            rocket::tokio::spawn,
+        ))
+        .data(conn)
+        .data(user)
+        .data(registration.inner().clone())
+        .data(mail.inner().clone());
+    Json(schema.execute(request).await)
+}
+
+/// An in-browser IDE to explore the GraphQL schema.
+#[get("/graphql")]
+pub(crate) fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The query GraphiQL sends to explore the schema.
+    const INTROSPECTION: &str = r#"
+        query IntrospectionQuery {
+            __schema {
+                queryType { name }
+                mutationType { name }
+                subscriptionType { name }
+                types { ...FullType }
+                directives { name description locations args { ...InputValue } }
+            }
+        }
+        fragment FullType on __Type {
+            kind name description
+            fields(includeDeprecated: true) {
+                name description args { ...InputValue } type { ...TypeRef } isDeprecated deprecationReason
+            }
+            inputFields { ...InputValue }
+            interfaces { ...TypeRef }
+            enumValues(includeDeprecated: true) { name description isDeprecated deprecationReason }
+            possibleTypes { ...TypeRef }
+        }
+        fragment InputValue on __InputValue { name description type { ...TypeRef } defaultValue }
+        fragment TypeRef on __Type {
+            kind name
+            ofType { kind name ofType { kind name ofType { kind name ofType { kind name
+                ofType { kind name ofType { kind name ofType { kind name } } } } } } }
+        }"#;
+
+    /// A query going from a question to its answers and back the given number of times.
+    fn nested(levels: usize) -> String {
+        let mut query = "id".to_string();
+        for _ in 0..levels {
+            query = format!("id answers {{ id question {{ {} }} }}", query);
+        }
+        format!("{{ question(id: 1) {{ {} }} }}", query)
+    }
+
+    async fn errors(query: &str) -> Vec<String> {
+        schema()
+            .execute(query)
+            .await
+            .errors
+            .into_iter()
+            .map(|e| e.message)
+            .collect()
+    }
+
+    #[rocket::async_test]
+    async fn introspection_is_allowed() {
+        assert_eq!(errors(INTROSPECTION).await, Vec::<String>::new());
+    }
+
+    #[rocket::async_test]
+    async fn shallow_query_passes_the_limits() {
+        // Resolving fails without a database, but only after the limits were checked
+        let listing = "{ questions { id title author { username } answers { id html author { username } } } }";
+        for query in [nested(1), listing.to_string()] {
+            let errors = errors(&query).await;
+            assert!(
+                !errors.iter().any(|e| e.starts_with("Query is")),
+                "{:?}",
+                errors
+            );
+        }
+    }
+
+    #[rocket::async_test]
+    async fn deeply_nested_query_is_rejected() {
+        let mut query = "name".to_string();
+        for _ in 0..MAX_DEPTH {
+            query = format!("ofType {{ {} }}", query);
+        }
+        let query = format!("{{ __type(name: \"Query\") {{ {} }} }}", query);
+        assert_eq!(errors(&query).await, vec!["Query is nested too deep."]);
+    }
+
+    #[rocket::async_test]
+    async fn fanning_out_query_is_rejected() {
+        assert_eq!(errors(&nested(3)).await, vec!["Query is too complex."]);
+        assert_eq!(errors(&nested(10)).await, vec!["Query is too complex."]);
+    }
+}
@@ -0,0 +1,80 @@
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::{self, error::RecvError};
+use rocket::Shutdown;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// How many events a slow subscriber may fall behind before it has to resync.
+const CAPACITY: usize = 256;
+
+/// A change of a thread, pushed to everyone viewing it.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum LiveEvent {
+    /// A new answer was posted.
+    Answer { question: i32, answer: i32 },
+    /// The question, or one of its answers if `answer` is set, was voted on.
+    Score {
+        question: i32,
+        answer: Option<i32>,
+        score: i32,
+    },
+    /// An answer was accepted.
+    Accepted { question: i32, answer: i32 },
+}
+
+impl LiveEvent {
+    fn question(&self) -> i32 {
+        match self {
+            LiveEvent::Answer { question, .. }
+            | LiveEvent::Score { question, .. }
+            | LiveEvent::Accepted { question, .. } => *question,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::Answer { .. } => "answer",
+            LiveEvent::Score { .. } => "score",
+            LiveEvent::Accepted { .. } => "accepted",
+        }
+    }
+}
+
+/// The in-process channel between the write actions of the database and the open event streams.
+fn channel() -> &'static broadcast::Sender<LiveEvent> {
+    static CHANNEL: OnceLock<broadcast::Sender<LiveEvent>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// Push an event to everyone viewing its thread, called once the change is committed.
+pub(crate) fn publish(event: LiveEvent) {
+    // Sending only fails if nobody is listening
+    let _ = channel().send(event);
+}
+
+/// Stream the changes of a thread as server-sent events.
+/// A `resync` event asks the page to reload the thread as events were missed.
+#[get("/q/<id>/events")]
+pub(crate) fn events(id: i32, mut shutdown: Shutdown) -> EventStream![] {
+    let mut events = channel().subscribe();
+    EventStream! {
+        loop {
+            let event = select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => {
+                        yield Event::data("").event("resync");
+                        continue;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+            if event.question() == id {
+                yield Event::json(&event).event(event.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use lettre::message::header::{Header, HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MessageBuilder};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rocket::fairing::AdHoc;
+use serde::Deserialize;
+use std::error::Error;
+use std::sync::{Arc, OnceLock};
+
+/// The `List-Unsubscribe` header, the link mail clients offer to stop emails from this board.
+#[derive(Debug, Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(ListUnsubscribe(
+            s.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .into(),
+        ))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// The `List-Unsubscribe-Post` header, telling mail clients to unsubscribe with a single POST to the link.
+#[derive(Debug, Clone)]
+struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(ListUnsubscribePost)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".into())
+    }
+}
+
+/// A way of delivering emails to their recipients.
+#[rocket::async_trait]
+pub(crate) trait Mailer: Send + Sync {
+    async fn deliver(&self, message: Message) -> Result<(), String>;
+}
+
+/// Delivers emails through an SMTP relay.
+pub(crate) struct SmtpMailer(AsyncSmtpTransport<Tokio1Executor>);
+
+#[rocket::async_trait]
+impl Mailer for SmtpMailer {
+    async fn deliver(&self, message: Message) -> Result<(), String> {
+        self.0
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Drops emails as `.eml` files into a local directory, useful during development.
+pub(crate) struct FileMailer(AsyncFileTransport<Tokio1Executor>);
+
+#[rocket::async_trait]
+impl Mailer for FileMailer {
+    async fn deliver(&self, message: Message) -> Result<(), String> {
+        self.0
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The backend used to deliver emails.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+enum MailerConfig {
+    Smtp {
+        host: String,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+    },
+    File {
+        directory: String,
+    },
+}
+
+/// The configuration of outgoing emails, read from the `mail` table in `Rocket.toml`.
+#[derive(Debug, Clone, Deserialize)]
+struct MailConfig {
+    from: String,
+    /// The url this board is reachable at, used to create links in emails.
+    public_url: String,
+    #[serde(flatten)]
+    mailer: MailerConfig,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            from: "RustOverflow <noreply@localhost>".into(),
+            public_url: "http://localhost:8000".into(),
+            mailer: MailerConfig::File {
+                directory: "mail".into(),
+            },
+        }
+    }
+}
+
+/// The url this board is reachable at as read from the configuration, the default one until it was read.
+static PUBLIC_URL: OnceLock<String> = OnceLock::new();
+
+/// Return the configured url this board is reachable at, without a trailing slash.
+pub(crate) fn public_url() -> &'static str {
+    PUBLIC_URL
+        .get_or_init(|| MailConfig::default().public_url)
+        .trim_end_matches('/')
+}
+
+/// Sends emails from this board using the configured mailer.
+#[derive(Clone)]
+pub(crate) struct Mail {
+    mailer: Arc<dyn Mailer>,
+    from: Mailbox,
+    public_url: String,
+}
+
+impl Mail {
+    /// Create an absolute link to the given path on this board.
+    pub(crate) fn link(&self, path: &str) -> String {
+        format!("{}{}", self.public_url.trim_end_matches('/'), path)
+    }
+
+    fn builder(&self, to: &str, subject: &str) -> Result<MessageBuilder, String> {
+        Ok(Message::builder()
+            .from(self.from.clone())
+            .to(to
+                .parse()
+                .map_err(|_| "Invalid email address".to_string())?)
+            .subject(subject))
+    }
+
+    /// Send a plain text email to the given address.
+    pub(crate) async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), String> {
+        let message = self
+            .builder(to, subject)?
+            .body(body)
+            .map_err(|e| e.to_string())?;
+        self.mailer.deliver(message).await
+    }
+
+    /// Send a plain text email to a subscriber, which mail clients let them unsubscribe from with one click.
+    pub(crate) async fn send_to_subscriber(
+        &self,
+        to: &str,
+        subject: &str,
+        body: String,
+        unsubscribe: String,
+    ) -> Result<(), String> {
+        let message = self
+            .builder(to, subject)?
+            .header(ListUnsubscribe(unsubscribe))
+            .header(ListUnsubscribePost)
+            .body(body)
+            .map_err(|e| e.to_string())?;
+        self.mailer.deliver(message).await
+    }
+}
+
+/// A fairing setting up the mailer, emails are dropped into the `mail` directory if none is configured.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Mail", |rocket| async {
+        let config = rocket
+            .figment()
+            .extract_inner::<MailConfig>("mail")
+            .unwrap_or_default();
+        if PUBLIC_URL.set(config.public_url.clone()).is_err() {
+            warn!("The public url was used before it was configured");
+        }
+        let mailer: Arc<dyn Mailer> = match config.mailer {
+            MailerConfig::Smtp {
+                host,
+                port,
+                username,
+                password,
+            } => {
+                let mut builder = match AsyncSmtpTransport::<Tokio1Executor>::relay(&host) {
+                    Ok(builder) => builder,
+                    Err(e) => {
+                        error!("Invalid SMTP relay {}: {}", host, e);
+                        return Err(rocket);
+                    }
+                };
+                if let Some(port) = port {
+                    builder = builder.port(port);
+                }
+                if let (Some(username), Some(password)) = (username, password) {
+                    builder = builder.credentials(Credentials::new(username, password));
+                }
+                Arc::new(SmtpMailer(builder.build()))
+            }
+            MailerConfig::File { directory } => {
+                if let Err(e) = std::fs::create_dir_all(&directory) {
+                    error!("Could not create mail directory {}: {}", directory, e);
+                    return Err(rocket);
+                }
+                Arc::new(FileMailer(AsyncFileTransport::new(directory)))
+            }
+        };
+        let from = match config.from.parse() {
+            Ok(from) => from,
+            Err(_) => {
+                error!("Invalid sender address {}", config.from);
+                return Err(rocket);
+            }
+        };
+        Ok(rocket.manage(Mail {
+            mailer,
+            from,
+            public_url: config.public_url,
+        }))
+    })
+}
@@ -1,13 +1,37 @@
+mod api;
+mod auth;
 mod backend;
+mod badges;
+mod bounties;
+mod collections;
 mod db;
+mod duplicates;
+mod feed;
 mod frontend;
+mod graphql;
+mod live;
+mod mail;
+mod markdown;
+mod notifications;
+mod oidc;
+mod openapi;
+mod ranking;
+mod related;
+mod sanitize;
+mod settings;
+mod subscriptions;
+mod tag_suggestions;
+#[cfg(test)]
+mod testing;
+mod views;
+mod webhooks;
 
 #[macro_use]
 extern crate rocket;
 #[macro_use]
 extern crate diesel;
 
-use crate::db::DbConn;
+use crate::db::{DbConn, TaskConn};
 use chrono::NaiveDateTime;
 use rocket::fs::{relative, FileServer};
 use rocket_dyn_templates::handlebars::{
@@ -42,35 +66,6 @@ fn datetime_helper(
     Ok(())
 }
 
-/// Parses the markdown in a given string and transforms it into corresponding HTML code.
-fn markdown_helper(
-    h: &Helper,
-    _: &Handlebars,
-    _: &Context,
-    _: &mut RenderContext,
-    out: &mut dyn Output,
-) -> HelperResult {
-    use comrak::plugins::syntect::SyntectAdapter;
-    use comrak::{markdown_to_html_with_plugins, ComrakOptions, ComrakPlugins};
-    use serde_json::Value;
-
-    let raw_value = h.param(0).unwrap().value();
-
-    let adapter = SyntectAdapter::new("Solarized (light)");
-    let options = ComrakOptions::default();
-    let mut plugins = ComrakPlugins::default();
-    plugins.render.codefence_syntax_highlighter = Some(&adapter);
-
-    if let Value::String(md) = raw_value {
-        out.write(&markdown_to_html_with_plugins(
-            md.as_str(),
-            &options,
-            &plugins,
-        ))?;
-    }
-    Ok(())
-}
-
 #[launch]
 fn rocket() -> _ {
     rocket::build()
@@ -81,8 +76,15 @@ fn rocket() -> _ {
                 frontend::index,
                 frontend::tagged_question,
                 frontend::thread,
+                live::events,
+                frontend::my_feed,
+                frontend::profile,
+                feed::index,
+                feed::tagged_question,
+                feed::thread,
                 backend::login,
                 backend::register,
+                backend::verify,
                 backend::logout,
                 backend::ask_question,
                 backend::answer_question,
@@ -91,17 +93,92 @@ fn rocket() -> _ {
                 backend::upvote_question,
                 backend::downvote_question,
                 backend::solve_question,
+                oidc::login,
+                oidc::callback,
+                settings::settings,
+                settings::create_token,
+                settings::revoke_token,
+                settings::notifications,
+                notifications::notifications,
+                notifications::mark_read,
+                notifications::mark_all_read,
+                subscriptions::follow,
+                subscriptions::unfollow,
+                subscriptions::follow_user,
+                collections::collections,
+                collections::create_collection,
+                collections::collection,
+                collections::edit_collection,
+                collections::delete_collection,
+                collections::bookmark,
+                collections::move_up,
+                collections::move_down,
+                collections::remove_bookmark,
+                subscriptions::unfollow_user,
+                subscriptions::unsubscribe,
+                subscriptions::confirm_unsubscribe,
+                settings::change_email,
+                settings::subscriptions,
+                settings::tags,
+                graphql::graphql,
+                graphql::graphiql,
+                webhooks::webhooks,
+                webhooks::create_webhook,
+                webhooks::delete_webhook,
+                webhooks::pause_webhook,
+                webhooks::resume_webhook,
+                webhooks::deliveries,
+                webhooks::redeliver,
+                frontend::featured,
+                badges::badges,
+                bounties::start_bounty,
+                bounties::award_bounty,
+                duplicates::duplicates,
+                markdown::rerender,
+                markdown::preview,
+                tag_suggestions::suggest_tags,
                 style
             ],
         )
+        .mount(
+            "/api/v1",
+            routes![
+                api::questions,
+                api::question,
+                api::ask,
+                api::answers,
+                api::answer,
+                api::tags,
+                api::suggest_tags,
+                api::me,
+                api::vote_question,
+                api::vote_answer,
+                api::accept
+            ],
+        )
+        .mount("/api", routes![openapi::openapi, openapi::explorer])
+        .register("/api", catchers![api::catcher])
+        .manage(graphql::schema())
+        .manage(views::ViewCounter::default())
         .attach(DbConn::fairing())
+        .attach(TaskConn::fairing())
+        .attach(auth::fairing())
+        .attach(backend::fairing())
+        .attach(mail::fairing())
+        .attach(oidc::fairing())
+        .attach(subscriptions::fairing())
+        .attach(webhooks::fairing())
+        .attach(bounties::fairing())
+        .attach(badges::fairing())
+        .attach(views::fairing())
+        .attach(sanitize::fairing())
+        .attach(markdown::fairing())
+        .attach(ranking::fairing())
+        .attach(related::fairing())
         .attach(Template::custom(|engines: &mut Engines| {
             engines
                 .handlebars
                 .register_helper("to_duration", Box::new(datetime_helper));
-            engines
-                .handlebars
-                .register_helper("as_markdown", Box::new(markdown_helper));
         }))
         .attach(SassSheet::fairing())
 }
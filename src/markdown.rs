@@ -0,0 +1,342 @@
+use crate::backend::Admin;
+use crate::db::models::Login;
+use crate::db::DbConn;
+use crate::sanitize;
+use comrak::nodes::{Ast, AstNode, NodeLink, NodeValue};
+use comrak::plugins::syntect::SyntectAdapter;
+use comrak::{format_html_with_plugins, parse_document, Arena, ComrakOptions, ComrakPlugins};
+use rocket::fairing::AdHoc;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::content::Html;
+use rocket::response::Redirect;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::OnceLock;
+
+/// The titles of the questions referenced as `#<id>`, references to other ids stay text.
+pub(crate) type Titles = HashMap<i32, String>;
+
+/// The theme fenced code blocks are highlighted with.
+const THEME: &str = "Solarized (light)";
+
+/// The highlighter shared by all renderings, loading its syntaxes and themes is expensive.
+static HIGHLIGHTER: OnceLock<SyntectAdapter<'static>> = OnceLock::new();
+
+/// Path roots which are documented with the standard library instead of on docs.rs.
+const STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// A piece of text the renderer turns into a link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Extension {
+    /// A rustc error code like `E0382`, linked to the error index.
+    ErrorCode(String),
+    /// A path like `std::vec::Vec` or `rocket::Request`, linked to a search of its docs.
+    Path(String),
+    /// A `crate:serde` style link to the page of the crate.
+    Crate(String),
+    /// A `#123` reference to another question, shown with its title.
+    Question(i32),
+}
+
+impl Extension {
+    fn url(&self) -> String {
+        match self {
+            Extension::ErrorCode(code) => {
+                format!("https://doc.rust-lang.org/error_codes/{}.html", code)
+            }
+            Extension::Path(path) => {
+                let root = path.split("::").next().unwrap_or_default();
+                if STD_CRATES.contains(&root) {
+                    format!("https://doc.rust-lang.org/{}/?search={}", root, path)
+                } else {
+                    format!("https://docs.rs/{0}/latest/{0}/?search={1}", root, path)
+                }
+            }
+            Extension::Crate(name) => format!("https://crates.io/crates/{}", name),
+            Extension::Question(id) => format!("/q/{}", id),
+        }
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The length of the identifier at the start of the text.
+fn ident_len(text: &str) -> usize {
+    if !text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return 0;
+    }
+    text.find(|c: char| !is_ident(c)).unwrap_or(text.len())
+}
+
+/// Match an extension at the start of the text, returning it with the length of the matched text.
+fn extension_at(text: &str, titles: &Titles) -> Option<(Extension, usize)> {
+    let word_end = |len: usize| !text[len..].starts_with(is_ident);
+    if let Some(rest) = text.strip_prefix('#') {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let id: i32 = rest[..digits].parse().ok()?;
+        return (word_end(digits + 1) && titles.contains_key(&id))
+            .then_some((Extension::Question(id), digits + 1));
+    }
+    if let Some(rest) = text.strip_prefix("crate:") {
+        let len = rest
+            .find(|c: char| !is_ident(c) && c != '-')
+            .unwrap_or(rest.len());
+        return (len > 0).then(|| (Extension::Crate(rest[..len].to_string()), len + 6));
+    }
+    let len = ident_len(text);
+    if len == 0 {
+        return None;
+    }
+    let word = &text[..len];
+    if len == 5 && word.starts_with('E') && word[1..].chars().all(|c| c.is_ascii_digit()) {
+        return Some((Extension::ErrorCode(word.to_string()), len));
+    }
+    // Paths start with a crate, which is lowercase, and have at least two segments
+    if !word.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let mut end = len;
+    while let Some(rest) = text[end..].strip_prefix("::") {
+        match ident_len(rest) {
+            0 => break,
+            segment => end += 2 + segment,
+        }
+    }
+    (end > len).then(|| (Extension::Path(text[..end].to_string()), end))
+}
+
+/// A part of a text, either plain or to be linked.
+enum Segment {
+    Text(String),
+    Link(Extension, String),
+}
+
+/// Split a text into the extensions it contains and the plain text between them.
+fn segments(text: &str, titles: &Titles) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut plain = 0;
+    let mut i = 0;
+    while i < text.len() {
+        // Extensions only start at the beginning of a word
+        let boundary = !text[..i].ends_with(|c: char| is_ident(c) || c == ':');
+        match extension_at(&text[i..], titles).filter(|_| boundary) {
+            Some((extension, len)) => {
+                if plain < i {
+                    segments.push(Segment::Text(text[plain..i].to_string()));
+                }
+                let shown = match &extension {
+                    Extension::Question(id) => titles[id].clone(),
+                    Extension::Crate(name) => name.clone(),
+                    _ => text[i..i + len].to_string(),
+                };
+                segments.push(Segment::Link(extension, shown));
+                i += len;
+                plain = i;
+            }
+            None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if plain < text.len() {
+        segments.push(Segment::Text(text[plain..].to_string()));
+    }
+    segments
+}
+
+fn new_node<'a>(arena: &'a Arena<AstNode<'a>>, value: NodeValue) -> &'a AstNode<'a> {
+    arena.alloc(AstNode::new(RefCell::new(Ast::new(value))))
+}
+
+fn link_node<'a>(arena: &'a Arena<AstNode<'a>>, extension: &Extension) -> &'a AstNode<'a> {
+    new_node(
+        arena,
+        NodeValue::Link(NodeLink {
+            url: extension.url().into_bytes(),
+            title: vec![],
+        }),
+    )
+}
+
+/// Turn the extensions in the text of a document into links, leaving existing links alone.
+fn link_extensions<'a>(arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, titles: &Titles) {
+    let nodes: Vec<&AstNode> = root
+        .descendants()
+        .filter(|node| {
+            !node.ancestors().skip(1).any(|a| {
+                matches!(
+                    a.data.borrow().value,
+                    NodeValue::Link(_) | NodeValue::Image(_)
+                )
+            })
+        })
+        .collect();
+    for node in nodes {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Text(text) => {
+                let text = String::from_utf8_lossy(&text);
+                let segments = segments(&text, titles);
+                if !segments.iter().any(|s| matches!(s, Segment::Link(..))) {
+                    continue;
+                }
+                for segment in segments {
+                    let piece = match segment {
+                        Segment::Text(plain) => {
+                            new_node(arena, NodeValue::Text(plain.into_bytes()))
+                        }
+                        Segment::Link(extension, shown) => {
+                            let link = link_node(arena, &extension);
+                            link.append(new_node(arena, NodeValue::Text(shown.into_bytes())));
+                            link
+                        }
+                    };
+                    node.insert_before(piece);
+                }
+                node.detach();
+            }
+            // Inline code consisting of nothing but a path or an error code is linked as a whole
+            NodeValue::Code(code) => {
+                let literal = String::from_utf8_lossy(&code.literal);
+                if let Some((extension, len)) = extension_at(&literal, titles) {
+                    if len == literal.len()
+                        && matches!(extension, Extension::Path(_) | Extension::ErrorCode(_))
+                    {
+                        let link = link_node(arena, &extension);
+                        node.insert_before(link);
+                        link.append(node);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Return the ids of the questions referenced as `#<id>` in a text.
+pub(crate) fn references(md: &str) -> HashSet<i32> {
+    md.match_indices('#')
+        .filter(|&(i, _)| !md[..i].ends_with(is_ident))
+        .filter_map(|(i, _)| {
+            let rest = &md[i + 1..];
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            rest[..digits].parse().ok()
+        })
+        .collect()
+}
+
+/// Render markdown into HTML, highlighting fenced code blocks and linking error codes, paths,
+/// crates and references to the questions with the given titles.
+/// Questions and answers are rendered once when they are written, their HTML is stored with them.
+pub(crate) fn render(md: &str, titles: &Titles) -> String {
+    render_with_options(md, titles, &ComrakOptions::default())
+}
+
+/// Render markdown into HTML with the given options, the result is sanitized whatever they allow.
+pub(crate) fn render_with_options(md: &str, titles: &Titles, options: &ComrakOptions) -> String {
+    let mut plugins = ComrakPlugins::default();
+    plugins.render.codefence_syntax_highlighter =
+        Some(HIGHLIGHTER.get_or_init(|| SyntectAdapter::new(THEME)));
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, md, options);
+    link_extensions(&arena, root, titles);
+    let mut html = vec![];
+    format_html_with_plugins(root, options, &mut html, &plugins)
+        .expect("writing to a vector cannot fail");
+    sanitize::clean(&String::from_utf8_lossy(&html))
+}
+
+/// Render the markdown of all questions and answers to HTML again and return how many were rendered.
+/// They are rendered in batches, so the database is only locked to store each batch.
+/// If `only_missing` is set, only the ones without HTML are rendered, i.e. the ones written before it was stored.
+async fn rerender_all(conn: &DbConn, only_missing: bool) -> Result<usize, (Status, String)> {
+    let mut rendered = 0;
+    let mut after = 0;
+    loop {
+        let batch = conn.rerender_questions(after, only_missing).await?;
+        match batch.last() {
+            Some(&last) => after = last,
+            None => break,
+        }
+        rendered += batch.len();
+    }
+    after = 0;
+    loop {
+        let batch = conn.rerender_answers(after, only_missing).await?;
+        match batch.last() {
+            Some(&last) => after = last,
+            None => break,
+        }
+        rendered += batch.len();
+    }
+    Ok(rendered)
+}
+
+/// Render the posts without HTML, or all of them if the sanitization policy changed since they were rendered,
+/// and remember the policy they were rendered with.
+async fn render_stale(conn: &DbConn) -> Result<usize, (Status, String)> {
+    let policy = sanitize::policy_hash();
+    let changed = conn.rendered_policy().await?.as_ref() != Some(&policy);
+    let rendered = rerender_all(conn, !changed).await?;
+    if changed {
+        conn.set_rendered_policy(policy).await?;
+    }
+    Ok(rendered)
+}
+
+/// Render markdown in the background, logging how many posts were rendered.
+fn spawn_rendering(
+    rendering: impl Future<Output = Result<usize, (Status, String)>> + Send + 'static,
+) {
+    rocket::tokio::spawn(async move {
+        match rendering.await {
+            Ok(0) => {}
+            Ok(rendered) => info!("Rendered the markdown of {} posts", rendered),
+            Err((_, e)) => warn!("Could not render markdown: {}", e),
+        }
+    });
+}
+
+/// A fairing starting the task that renders the questions and answers written before their HTML was stored,
+/// or all of them after the sanitization policy changed.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Markdown", |rocket| {
+        Box::pin(async move {
+            // Rendering may take long, so it has a connection of its own, returned to the pool when it's done
+            match DbConn::get_one(rocket).await {
+                Some(conn) => spawn_rendering(async move { render_stale(&conn).await }),
+                None => error!("Rendering markdown needs a database connection"),
+            }
+        })
+    })
+}
+
+/// Render all questions and answers again in the background, e.g. after the renderer changed.
+/// The connection of the request is kept until all are rendered. Only administrators may do so.
+#[post("/admin/markdown/rerender")]
+pub(crate) async fn rerender(_admin: Admin, conn: DbConn) -> Redirect {
+    spawn_rendering(async move { rerender_all(&conn, false).await });
+    Redirect::to("/settings")
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct PreviewForm {
+    text: String,
+}
+
+/// Render markdown as a question or answer would be, for the preview of the forms.
+#[post("/preview", data = "<form>")]
+pub(crate) async fn preview(
+    _user: Login,
+    conn: DbConn,
+    form: Form<PreviewForm>,
+) -> Result<Html<String>, (Status, String)> {
+    Ok(Html(conn.render_markdown(form.into_inner().text).await?))
+}
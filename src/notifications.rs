@@ -0,0 +1,58 @@
+use crate::db::models::{Login, Notification};
+use crate::db::DbConn;
+use crate::oidc::OidcConfig;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+
+/// Count the unread notifications of the user for the badge in the navbar.
+pub(crate) async fn unread(conn: &DbConn, user: &Option<Login>) -> Result<i64, (Status, String)> {
+    match user {
+        Some(user) => conn.unread_notifications(user.id).await,
+        None => Ok(0),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NotificationsCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    notifications: Vec<Notification>,
+}
+
+#[get("/notifications")]
+pub(crate) async fn notifications(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    Ok(Template::render(
+        "notifications",
+        NotificationsCtx {
+            unread: conn.unread_notifications(user.id).await?,
+            notifications: conn.notifications(user.id).await?,
+            user: Some(user.username),
+            sso: oidc.is_some(),
+        },
+    ))
+}
+
+#[post("/notifications/<id>/read")]
+pub(crate) async fn mark_read(
+    user: Login,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.mark_notifications_read(user.id, Some(id)).await?;
+    Ok(Redirect::to(uri!(notifications)))
+}
+
+#[post("/notifications/read")]
+pub(crate) async fn mark_all_read(user: Login, conn: DbConn) -> Result<Redirect, (Status, String)> {
+    conn.mark_notifications_read(user.id, None).await?;
+    Ok(Redirect::to(uri!(notifications)))
+}
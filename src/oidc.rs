@@ -0,0 +1,510 @@
+use crate::auth::random_token;
+use crate::db::DbConn;
+use chrono::Utc;
+use reqwest::Url;
+use rocket::fairing::AdHoc;
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::response::Redirect;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The configuration of the OpenID Connect provider, read from the `oidc` table in `Rocket.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct OidcConfig {
+    /// The issuer url, used to discover the endpoints of the provider.
+    issuer: String,
+    client_id: String,
+    client_secret: Option<String>,
+    /// The url of the `callback` route as registered at the provider.
+    redirect_url: String,
+    #[serde(default = "default_scopes")]
+    scopes: String,
+}
+
+fn default_scopes() -> String {
+    "openid profile".into()
+}
+
+/// The endpoints published by the provider at `/.well-known/openid-configuration`.
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+/// The data remembered between redirecting to the provider and its callback.
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingLogin {
+    state: String,
+    verifier: String,
+    /// Sent to the provider, which puts it into the ID token to bind the token to this login.
+    nonce: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: String,
+}
+
+/// The audience of an ID token, either a single client or several of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The claims of an ID token which are checked before logging in.
+#[derive(Debug, Deserialize)]
+struct IdClaims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    /// The client the token was issued to, required if there are several audiences.
+    azp: Option<String>,
+    /// The expiry as seconds since the epoch.
+    exp: i64,
+    nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    name: Option<String>,
+}
+
+/// A fairing reading the optional provider configuration into the managed state.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_ignite("OpenID Connect", |rocket| async {
+        let config = rocket.figment().extract_inner::<OidcConfig>("oidc").ok();
+        rocket.manage(config)
+    })
+}
+
+fn provider_error<E>(_: E) -> (Status, String) {
+    (Status::BadGateway, "Identity provider error".into())
+}
+
+fn configured(config: &Option<OidcConfig>) -> Result<&OidcConfig, (Status, String)> {
+    config
+        .as_ref()
+        .ok_or((Status::NotFound, "Single sign-on is not configured".into()))
+}
+
+async fn discover(config: &OidcConfig) -> Result<Discovery, (Status, String)> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        config.issuer.trim_end_matches('/')
+    );
+    reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(provider_error)?
+        .json()
+        .await
+        .map_err(provider_error)
+}
+
+fn invalid_token(reason: &str) -> (Status, String) {
+    (
+        Status::Unauthorized,
+        format!("Invalid ID token: {}", reason),
+    )
+}
+
+/// Check the ID token returned along with the access token and return the subject it identifies.
+/// Its signature is not verified, as the token is received directly from the token endpoint of the provider.
+/// OpenID Connect Core 3.1.3.7 allows relying on the TLS connection instead, so the issuer has to be configured
+/// with an https url outside of development. The issuer, the audience, the expiry and the nonce are checked.
+fn validate_id_token(
+    config: &OidcConfig,
+    token: &str,
+    nonce: &str,
+    now: i64,
+) -> Result<String, (Status, String)> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| invalid_token("not a JWT"))?;
+    let claims: IdClaims = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(|| invalid_token("malformed claims"))?;
+
+    if claims.iss != config.issuer {
+        return Err(invalid_token("wrong issuer"));
+    }
+    let audience_matches = match &claims.aud {
+        Audience::One(client) => *client == config.client_id,
+        Audience::Many(clients) => {
+            clients.contains(&config.client_id)
+                && (clients.len() == 1 || claims.azp.as_ref() == Some(&config.client_id))
+        }
+    };
+    if !audience_matches {
+        return Err(invalid_token("issued to another client"));
+    }
+    if claims.exp <= now {
+        return Err(invalid_token("expired"));
+    }
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(invalid_token("wrong nonce"));
+    }
+    Ok(claims.sub)
+}
+
+/// Redirect to the provider to start an authorization code flow secured with PKCE.
+#[get("/oidc/login")]
+pub(crate) async fn login(
+    config: &State<Option<OidcConfig>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, (Status, String)> {
+    let config = configured(config)?;
+    let discovery = discover(config).await?;
+
+    let pending = PendingLogin {
+        state: random_token(32),
+        verifier: random_token(64),
+        nonce: random_token(32),
+    };
+    let challenge = base64::encode_config(
+        Sha256::digest(pending.verifier.as_bytes()),
+        base64::URL_SAFE_NO_PAD,
+    );
+    let url = Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", &config.client_id),
+            ("redirect_uri", &config.redirect_url),
+            ("scope", &config.scopes),
+            ("state", &pending.state),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+            ("nonce", &pending.nonce),
+        ],
+    )
+    .map_err(provider_error)?;
+
+    // The provider redirects back cross-site, so a strict cookie would not be sent along.
+    let mut cookie = Cookie::new("OIDC", serde_json::to_string(&pending).unwrap());
+    cookie.set_same_site(SameSite::Lax);
+    cookies.add_private(cookie);
+    Ok(Redirect::to(url.to_string()))
+}
+
+/// Exchange the authorization code and log in the user linked to the identity.
+#[get("/oidc/callback?<code>&<state>&<error>")]
+pub(crate) async fn callback(
+    conn: DbConn,
+    config: &State<Option<OidcConfig>>,
+    cookies: &CookieJar<'_>,
+    code: Option<&str>,
+    state: Option<&str>,
+    error: Option<&str>,
+) -> Result<Redirect, (Status, String)> {
+    let config = configured(config)?;
+    let pending: PendingLogin = cookies
+        .get_private("OIDC")
+        .and_then(|c| serde_json::from_str(c.value()).ok())
+        .ok_or((Status::BadRequest, "No login in progress".into()))?;
+    cookies.remove_private(Cookie::named("OIDC"));
+
+    if let Some(error) = error {
+        return Err((Status::Unauthorized, format!("Login failed: {}", error)));
+    }
+    if state != Some(pending.state.as_str()) {
+        return Err((Status::BadRequest, "Invalid login state".into()));
+    }
+    let code = code.ok_or((Status::BadRequest, "Missing authorization code".into()))?;
+
+    let discovery = discover(config).await?;
+    let client = reqwest::Client::new();
+    let mut params = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", &config.redirect_url),
+        ("client_id", &config.client_id),
+        ("code_verifier", &pending.verifier),
+    ];
+    if let Some(secret) = &config.client_secret {
+        params.push(("client_secret", secret));
+    }
+    let token: TokenResponse = client
+        .post(&discovery.token_endpoint)
+        .form(&params)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(provider_error)?
+        .json()
+        .await
+        .map_err(provider_error)?;
+    let subject = validate_id_token(
+        config,
+        &token.id_token,
+        &pending.nonce,
+        Utc::now().timestamp(),
+    )?;
+    let info: UserInfo = client
+        .get(&discovery.userinfo_endpoint)
+        .bearer_auth(token.access_token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(provider_error)?
+        .json()
+        .await
+        .map_err(provider_error)?;
+
+    // The user info has to describe the same user as the ID token, see OpenID Connect Core 5.3.2
+    if info.sub != subject {
+        return Err((
+            Status::Unauthorized,
+            "The user info belongs to another user".into(),
+        ));
+    }
+
+    let username = info
+        .preferred_username
+        .or(info.name)
+        .unwrap_or_else(|| info.sub.clone());
+    let login = conn
+        .external_login(config.issuer.clone(), info.sub, username)
+        .await?;
+    cookies.add_private(Cookie::new("User", serde_json::to_string(&login).unwrap()));
+    Ok(Redirect::to("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::Login;
+    use crate::db::test_figment;
+    use crate::testing::{serve, Received};
+    use rocket::figment::providers::Serialized;
+    use rocket::local::asynchronous::Client;
+    use rocket::tokio::sync::mpsc::UnboundedReceiver;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    const ISSUER: &str = "https://id.example.org";
+    const CLIENT: &str = "rustoverflow";
+
+    fn config(issuer: &str) -> OidcConfig {
+        OidcConfig {
+            issuer: issuer.into(),
+            client_id: CLIENT.into(),
+            client_secret: Some("secret".into()),
+            redirect_url: "http://localhost:8000/oidc/callback".into(),
+            scopes: default_scopes(),
+        }
+    }
+
+    /// An unsigned JWT with the given claims.
+    fn jwt(claims: &Value) -> String {
+        let encode =
+            |json: &Value| base64::encode_config(json.to_string(), base64::URL_SAFE_NO_PAD);
+        format!("{}.{}.", encode(&json!({"alg": "none"})), encode(claims))
+    }
+
+    fn claims(issuer: &str, nonce: &str) -> Value {
+        json!({
+            "iss": issuer,
+            "sub": "alice-id",
+            "aud": CLIENT,
+            "exp": Utc::now().timestamp() + 300,
+            "nonce": nonce,
+        })
+    }
+
+    /// Validate a token with the claims of a valid one changed.
+    fn validate(change: impl FnOnce(&mut Value)) -> Result<String, (Status, String)> {
+        let mut claims = claims(ISSUER, "nonce");
+        change(&mut claims);
+        validate_id_token(
+            &config(ISSUER),
+            &jwt(&claims),
+            "nonce",
+            Utc::now().timestamp(),
+        )
+    }
+
+    #[test]
+    fn accepts_valid_id_tokens() {
+        assert_eq!(validate(|_| {}), Ok("alice-id".to_string()));
+        assert!(validate(|c| c["aud"] = json!([CLIENT])).is_ok());
+        assert!(validate(|c| {
+            c["aud"] = json!([CLIENT, "other"]);
+            c["azp"] = json!(CLIENT);
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_id_tokens() {
+        let invalid =
+            |change: fn(&mut Value)| validate(change).unwrap_err().0 == Status::Unauthorized;
+        assert!(invalid(|c| c["iss"] = json!("https://evil.example.org")));
+        assert!(invalid(|c| c["aud"] = json!("other")));
+        assert!(invalid(|c| c["aud"] = json!([CLIENT, "other"])));
+        assert!(invalid(|c| {
+            c["aud"] = json!([CLIENT, "other"]);
+            c["azp"] = json!("other");
+        }));
+        assert!(invalid(|c| c["exp"] = json!(Utc::now().timestamp() - 1)));
+        assert!(invalid(|c| c["nonce"] = json!("replayed")));
+        assert!(invalid(|c| {
+            c.as_object_mut().unwrap().remove("nonce");
+        }));
+        assert!(invalid(|c| {
+            c.as_object_mut().unwrap().remove("sub");
+        }));
+        assert!(validate_id_token(&config(ISSUER), "garbage", "nonce", 0).is_err());
+    }
+
+    /// Start a provider issuing ID tokens with the claims set by the test, and the user info of alice.
+    async fn provider(claims: Arc<Mutex<Value>>) -> (String, UnboundedReceiver<Received>) {
+        serve(move |request| {
+            let issuer = format!("http://{}", request.header("Host").unwrap());
+            let path = request.path.split('?').next().unwrap();
+            match (request.method.as_str(), path) {
+                ("GET", "/.well-known/openid-configuration") => (
+                    200,
+                    json!({
+                        "issuer": issuer,
+                        "authorization_endpoint": format!("{}/authorize", issuer),
+                        "token_endpoint": format!("{}/token", issuer),
+                        "userinfo_endpoint": format!("{}/userinfo", issuer),
+                    })
+                    .to_string(),
+                ),
+                ("POST", "/token") => (
+                    200,
+                    json!({
+                        "access_token": "access",
+                        "token_type": "Bearer",
+                        "id_token": jwt(&claims.lock().unwrap()),
+                    })
+                    .to_string(),
+                ),
+                ("GET", "/userinfo")
+                    if request.header("Authorization") == Some("Bearer access") =>
+                {
+                    (
+                        200,
+                        json!({"sub": "alice-id", "preferred_username": "alice"}).to_string(),
+                    )
+                }
+                _ => (404, "{}".into()),
+            }
+        })
+        .await
+    }
+
+    /// Log in through the provider with the claims of its ID token changed,
+    /// returning the status of the callback and the logged in user.
+    async fn log_in(
+        client: &Client,
+        issuer: &str,
+        issued: &Mutex<Value>,
+        change: impl FnOnce(&mut Value),
+    ) -> (Status, Option<Login>) {
+        let response = client.get("/oidc/login").dispatch().await;
+        assert_eq!(response.status(), Status::SeeOther);
+        let location = Url::parse(response.headers().get_one("Location").unwrap()).unwrap();
+        assert_eq!(
+            location.as_str().split('?').next(),
+            Some(&*format!("{}/authorize", issuer))
+        );
+        let param = |name: &str| {
+            location
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .unwrap()
+        };
+
+        let mut new_claims = claims(issuer, &param("nonce"));
+        change(&mut new_claims);
+        *issued.lock().unwrap() = new_claims;
+
+        let response = client
+            .get(format!("/oidc/callback?code=code&state={}", param("state")))
+            .dispatch()
+            .await;
+        let login = client
+            .cookies()
+            .get_private("User")
+            .map(|cookie| serde_json::from_str(cookie.value()).unwrap());
+        (response.status(), login)
+    }
+
+    async fn client(issuer: &str) -> Client {
+        let figment = test_figment().merge(Serialized::global(
+            "oidc",
+            json!({
+                "issuer": issuer,
+                "client_id": CLIENT,
+                "client_secret": "secret",
+                "redirect_url": "http://localhost:8000/oidc/callback",
+            }),
+        ));
+        let rocket = rocket::custom(figment)
+            .attach(DbConn::fairing())
+            .attach(fairing())
+            .mount("/", routes![login, callback]);
+        Client::tracked(rocket).await.unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn logs_in_with_a_mock_provider() {
+        let claims = Arc::new(Mutex::new(Value::Null));
+        let (issuer, mut requests) = provider(claims.clone()).await;
+        let client = client(&issuer).await;
+
+        let (status, login) = log_in(&client, &issuer, &claims, |_| {}).await;
+        assert_eq!(status, Status::SeeOther);
+        assert_eq!(login.unwrap().username, "alice");
+
+        // The code is exchanged with the verifier of the challenge
+        let mut token_request = None;
+        while let Ok(request) = requests.try_recv() {
+            if request.path == "/token" {
+                token_request = Some(request);
+            }
+        }
+        let body = token_request.unwrap().body;
+        let form: Vec<(String, String)> = Url::parse(&format!("http://form/?{}", body))
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        assert!(form.contains(&("code".into(), "code".into())));
+        assert!(form.iter().any(|(key, _)| key == "code_verifier"));
+    }
+
+    #[rocket::async_test]
+    async fn rejects_logins_with_invalid_id_tokens() {
+        let claims = Arc::new(Mutex::new(Value::Null));
+        let (issuer, _requests) = provider(claims.clone()).await;
+        let client = client(&issuer).await;
+
+        let changes: [fn(&mut Value); 4] = [
+            |c| c["aud"] = json!("other"),
+            |c| c["exp"] = json!(Utc::now().timestamp() - 1),
+            |c| c["nonce"] = json!("replayed"),
+            // The user info describes another user than the ID token
+            |c| c["sub"] = json!("mallory-id"),
+        ];
+        for change in changes {
+            let (status, login) = log_in(&client, &issuer, &claims, change).await;
+            assert_eq!(status, Status::Unauthorized);
+            assert!(login.is_none());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::api::{self, AnswerBody, ApiError, AskBody, SuggestBody, VoteBody};
+use crate::backend::{self, AnswerForm, AskForm, LoginForm, RegisterForm};
+use crate::db::models::{Answer, DisplayQuestion, Login, Role, Tag};
+use crate::db::DbConn;
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// The OpenAPI document of the JSON API and the HTML forms, derived from the route and type definitions.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "RustOverflow",
+        description = "The JSON API of RustOverflow and the forms posted by its HTML pages."
+    ),
+    paths(
+        api::questions,
+        api::question,
+        api::ask,
+        api::answers,
+        api::answer,
+        api::tags,
+        api::suggest_tags,
+        api::me,
+        api::vote_question,
+        api::vote_answer,
+        api::accept,
+        backend::login,
+        backend::register,
+        backend::ask_question,
+        backend::answer_question,
+    ),
+    components(schemas(
+        DisplayQuestion,
+        Answer,
+        Tag,
+        Login,
+        Role,
+        ApiError,
+        AskBody,
+        SuggestBody,
+        AnswerBody,
+        VoteBody,
+        AskForm,
+        AnswerForm,
+        LoginForm,
+        RegisterForm,
+    )),
+    modifiers(&SecuritySchemes)
+)]
+pub(crate) struct ApiDoc;
+
+/// Adds the two ways of authenticating: API tokens and the login cookie of the browser.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // The crate has no license, which would otherwise be documented with an empty name.
+        openapi.info.license = None;
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "cookie",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("User"))),
+        );
+    }
+}
+
+#[get("/openapi.json")]
+pub(crate) fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExplorerCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+}
+
+/// A page to browse and try out the API, rendered in the browser from the OpenAPI document.
+#[get("/")]
+pub(crate) async fn explorer(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    Ok(Template::render(
+        "api",
+        ExplorerCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use utoipa::openapi::path::ParameterIn;
+
+    /// An operation as method, path with `{param}` segments and the names of its query parameters.
+    type Operation = (String, String, BTreeSet<String>);
+
+    /// The operations of the JSON API as mounted on the server.
+    fn mounted() -> BTreeSet<Operation> {
+        crate::rocket()
+            .routes()
+            .filter(|route| route.uri.path().starts_with("/api/v1/"))
+            .map(|route| {
+                let path = route
+                    .uri
+                    .path()
+                    .split('/')
+                    .map(|segment| match segment.strip_prefix('<') {
+                        Some(param) => format!("{{{}}}", param.trim_end_matches('>')),
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let query = route
+                    .uri
+                    .query()
+                    .into_iter()
+                    .flat_map(|query| query.split('&'))
+                    .map(|param| param.trim_matches(|c| c == '<' || c == '>').to_string())
+                    .collect();
+                (route.method.as_str().to_lowercase(), path, query)
+            })
+            .collect()
+    }
+
+    /// The operations of the JSON API in the OpenAPI document.
+    fn documented() -> BTreeSet<Operation> {
+        let mut operations = BTreeSet::new();
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            if !path.starts_with("/api/v1/") {
+                continue;
+            }
+            for (method, operation) in item.operations {
+                let method = serde_json::to_value(method).unwrap();
+                let query = operation
+                    .parameters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|param| matches!(param.parameter_in, ParameterIn::Query))
+                    .map(|param| param.name)
+                    .collect();
+                operations.insert((method.as_str().unwrap().to_string(), path.clone(), query));
+            }
+        }
+        operations
+    }
+
+    #[test]
+    fn documents_every_api_route() {
+        let mounted = mounted();
+        let documented = documented();
+        assert!(!mounted.is_empty());
+        assert_eq!(
+            mounted.difference(&documented).collect::<Vec<_>>(),
+            Vec::<&Operation>::new(),
+            "mounted but not documented"
+        );
+        assert_eq!(
+            documented.difference(&mounted).collect::<Vec<_>>(),
+            Vec::<&Operation>::new(),
+            "documented but not mounted"
+        );
+    }
+}
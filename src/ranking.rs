@@ -0,0 +1,116 @@
+use crate::db::models::{DisplayQuestion, HeatInput};
+use crate::db::{DbConn, TaskConn};
+use chrono::{Local, NaiveDateTime};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// How often the ranking of the hot questions is recomputed.
+const RANKING_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How many questions the ranking holds.
+const HOT_LIMIT: usize = 50;
+/// How fast questions cool down with age, higher values favour newer questions.
+const GRAVITY: f64 = 1.5;
+/// Answers given within this many hours count as recent.
+const RECENT_HOURS: i64 = 24;
+/// The weights of the score, the answers, the recent answers and the logarithm of the views.
+const SCORE_WEIGHT: f64 = 1.0;
+const ANSWER_WEIGHT: f64 = 2.0;
+const RECENT_ANSWER_WEIGHT: f64 = 3.0;
+const VIEW_WEIGHT: f64 = 1.0;
+
+/// The order of a question list.
+#[derive(FromFormField, Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Sort {
+    /// The newest questions first.
+    #[default]
+    Newest,
+    /// The most viewed questions first.
+    Views,
+    /// Only the hot questions, the hottest first.
+    Hot,
+}
+
+/// Load the questions in the given order, only the ones with one of the tags if any are given.
+/// The hot questions are loaded by their ranking, instead of loading all questions to keep the ranked ones.
+pub(crate) async fn questions(
+    conn: &DbConn,
+    tags: Vec<String>,
+    sort: Sort,
+) -> Result<Vec<DisplayQuestion>, (Status, String)> {
+    if sort == Sort::Hot {
+        let ranking = conn.hot_ranking().await?;
+        let mut questions = conn.questions_with_ids(ranking.clone()).await?;
+        if !tags.is_empty() {
+            questions.retain(|q| q.tags.iter().any(|t| tags.contains(&t.name)));
+        }
+        questions.sort_by_key(|q| ranking.iter().position(|&id| id == q.id));
+        return Ok(questions);
+    }
+    let mut questions = if tags.is_empty() {
+        conn.newest_questions().await?
+    } else {
+        conn.questions_with_tag(tags).await?
+    };
+    if sort == Sort::Views {
+        questions.sort_by_key(|q| Reverse(q.views));
+    }
+    Ok(questions)
+}
+
+/// The heat of a question: its score, answers, recent answers and views, cooling down with its age.
+/// Questions nobody cares about have no heat.
+fn heat(question: &HeatInput, now: NaiveDateTime) -> Option<f64> {
+    let recent = now - chrono::Duration::hours(RECENT_HOURS);
+    let recent_answers = question.answers.iter().filter(|&&t| t >= recent).count();
+    let interest = SCORE_WEIGHT * f64::from(question.score)
+        + ANSWER_WEIGHT * question.answers.len() as f64
+        + RECENT_ANSWER_WEIGHT * recent_answers as f64
+        + VIEW_WEIGHT * f64::from(question.views).ln_1p();
+    if interest <= 0.0 {
+        return None;
+    }
+    let age = (now - question.time).num_minutes().max(0) as f64 / 60.0;
+    Some(interest / (age + 2.0).powf(GRAVITY))
+}
+
+/// Compute the heat of all questions and store the hottest ones as the new ranking.
+async fn rank(conn: &DbConn) -> Result<(), (Status, String)> {
+    let now = Local::now().naive_local();
+    let mut ranking: Vec<(i32, f64)> = conn
+        .heat_inputs()
+        .await?
+        .iter()
+        .filter_map(|q| Some((q.id, heat(q, now)?)))
+        .collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking.truncate(HOT_LIMIT);
+    conn.store_hot_ranking(ranking).await
+}
+
+/// A fairing starting the task that recomputes the ranking of the hot questions.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Hot questions", |rocket| {
+        Box::pin(async move {
+            let conn = match rocket.state::<TaskConn>() {
+                Some(conn) => conn.clone(),
+                None => {
+                    error!("Hot questions need a database connection");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(RANKING_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err((_, e)) = rank(&conn).await {
+                        warn!("Could not rank the hot questions: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
@@ -0,0 +1,223 @@
+use crate::db::DbConn;
+use rocket::fairing::AdHoc;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// How many related questions are stored and shown for a question.
+pub(crate) const RELATED_LIMIT: usize = 5;
+/// How many questions waiting for their related questions are stored at once.
+pub(crate) const RELATED_BATCH: i64 = 100;
+/// How often the questions waiting for their related questions are looked for.
+const RELATED_INTERVAL: Duration = Duration::from_secs(30);
+/// Questions less similar than this are not related.
+const MIN_SIMILARITY: f64 = 0.1;
+/// How much shared tags and shared terms count towards the similarity.
+const TAG_WEIGHT: f64 = 0.4;
+const TERM_WEIGHT: f64 = 0.6;
+/// Words too common to tell questions apart.
+const STOP_WORDS: &[&str] = &[
+    "and", "are", "but", "can", "does", "for", "from", "get", "has", "have", "how", "not", "the",
+    "this", "that", "use", "using", "was", "what", "when", "where", "which", "why", "with", "you",
+];
+
+/// Return the ids of the questions linked in a text by their `/q/<id>` URLs.
+/// Only relative URLs and those under the url this board is reachable at are links to its questions,
+/// a `/q/<id>` URL elsewhere, like on another Q&A site, is not.
+pub(crate) fn linked_questions(text: &str, public_url: &str) -> HashSet<i32> {
+    let starts_url = |before: &str| {
+        before
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || "(<[\"'".contains(c))
+    };
+    text.match_indices("/q/")
+        .filter(|&(start, _)| {
+            let before = &text[..start];
+            starts_url(before)
+                || (!public_url.is_empty()
+                    && before.strip_suffix(public_url).is_some_and(starts_url))
+        })
+        .filter_map(|(start, _)| {
+            let digits: String = text[start + 3..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Split a text into lowercase terms, leaving out short and common words.
+pub(crate) fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// A question as compared to the others.
+#[derive(Debug, Clone)]
+pub(crate) struct Document {
+    pub(crate) id: i32,
+    /// How often each term occurs in the title and text.
+    pub(crate) terms: HashMap<String, usize>,
+    pub(crate) tags: HashSet<i32>,
+}
+
+impl Document {
+    pub(crate) fn new(id: i32, title: &str, text: &str, tags: HashSet<i32>) -> Self {
+        let mut counts = HashMap::new();
+        for term in terms(title).into_iter().chain(terms(text)) {
+            *counts.entry(term).or_default() += 1;
+        }
+        Document {
+            id,
+            terms: counts,
+            tags,
+        }
+    }
+
+    /// Weigh the terms by their frequency in the document and their rarity in the corpus.
+    fn weights(&self, idf: &HashMap<&str, f64>) -> HashMap<&str, f64> {
+        self.terms
+            .iter()
+            .map(|(term, &count)| {
+                let rarity = idf.get(term.as_str()).copied().unwrap_or_default();
+                (term.as_str(), count as f64 * rarity)
+            })
+            .collect()
+    }
+}
+
+/// The cosine similarity of two weighted term vectors.
+fn cosine(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(term, weight)| Some(weight * b.get(term)?))
+        .sum();
+    let norm = |v: &HashMap<&str, f64>| v.values().map(|w| w * w).sum::<f64>().sqrt();
+    match norm(a) * norm(b) {
+        n if n > 0.0 => dot / n,
+        _ => 0.0,
+    }
+}
+
+/// The Jaccard similarity of two sets of tags.
+fn jaccard(a: &HashSet<i32>, b: &HashSet<i32>) -> f64 {
+    match a.union(b).count() {
+        0 => 0.0,
+        union => a.intersection(b).count() as f64 / union as f64,
+    }
+}
+
+/// Return the documents most similar to the one with the given id together with their similarity,
+/// computed from the shared tags and the TF-IDF weighted terms.
+pub(crate) fn most_similar(id: i32, corpus: &[Document]) -> Vec<(i32, f64)> {
+    let target = match corpus.iter().find(|d| d.id == id) {
+        Some(target) => target,
+        None => return vec![],
+    };
+    let mut frequency: HashMap<&str, usize> = HashMap::new();
+    for document in corpus {
+        for term in document.terms.keys() {
+            *frequency.entry(term.as_str()).or_default() += 1;
+        }
+    }
+    let total = corpus.len() as f64;
+    let idf: HashMap<&str, f64> = frequency
+        .into_iter()
+        .map(|(term, df)| (term, ((total + 1.0) / (df as f64 + 1.0)).ln() + 1.0))
+        .collect();
+
+    let weights = target.weights(&idf);
+    let mut similar: Vec<(i32, f64)> = corpus
+        .iter()
+        .filter(|d| d.id != id)
+        .map(|d| {
+            let similarity = TAG_WEIGHT * jaccard(&target.tags, &d.tags)
+                + TERM_WEIGHT * cosine(&weights, &d.weights(&idf));
+            (d.id, similarity)
+        })
+        .filter(|&(_, similarity)| similarity >= MIN_SIMILARITY)
+        .collect();
+    similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+    similar.truncate(RELATED_LIMIT);
+    similar
+}
+
+/// A fairing starting the task that stores the links and related questions of new questions,
+/// and at first those of the questions asked before they were stored.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Related questions", |rocket| {
+        Box::pin(async move {
+            // Loading the corpus takes a while, so this task has a connection of its own
+            let conn = match DbConn::get_one(rocket).await {
+                Some(conn) => conn,
+                None => {
+                    error!("Related questions need a database connection");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(RELATED_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    loop {
+                        match conn.store_pending_related().await {
+                            Ok(stored) if stored < RELATED_BATCH as usize => break,
+                            Ok(_) => {}
+                            Err((_, e)) => {
+                                warn!("Could not store related questions: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::linked_questions;
+    use std::collections::HashSet;
+
+    const BOARD: &str = "https://rust.example.org";
+
+    fn links(text: &str) -> HashSet<i32> {
+        linked_questions(text, BOARD)
+    }
+
+    #[test]
+    fn finds_relative_links() {
+        assert_eq!(links("/q/1"), HashSet::from([1]));
+        assert_eq!(
+            links("See [this](/q/2) and <a href=\"/q/3#answer-4\">that</a>, or /q/5/."),
+            HashSet::from([2, 3, 5])
+        );
+    }
+
+    #[test]
+    fn finds_links_under_the_public_url() {
+        assert_eq!(
+            links("See https://rust.example.org/q/7 and <https://rust.example.org/q/8>"),
+            HashSet::from([7, 8])
+        );
+        assert_eq!(
+            linked_questions("See http://a.test/q/9", "http://a.test"),
+            HashSet::from([9])
+        );
+    }
+
+    #[test]
+    fn ignores_links_to_other_sites() {
+        assert!(links("https://stackoverflow.com/q/123").is_empty());
+        assert!(links("[elsewhere](//stackoverflow.com/q/123)").is_empty());
+        assert!(links("https://rust.example.org.evil.test/q/1").is_empty());
+        assert!(links("https://evil.test/?https://rust.example.org/q/1").is_empty());
+        assert!(links("/path/q/1 and /q/ and /q/x").is_empty());
+        assert!(linked_questions("https://stackoverflow.com/q/123", "").is_empty());
+    }
+}
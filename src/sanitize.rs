@@ -0,0 +1,357 @@
+use ammonia::{Builder, UrlRelative};
+use rocket::fairing::AdHoc;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Tags whose content is dropped together with them, they are never allowed.
+const CLEAN_CONTENT_TAGS: &[&str] = &["script", "style"];
+
+/// The HTML allowed in rendered questions and answers, configured in the `sanitize` section.
+/// Everything else is removed after rendering, even if the markdown renderer let it through.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct SanitizePolicy {
+    /// The allowed tags.
+    tags: HashSet<String>,
+    /// The allowed attributes of each tag, event handlers and `rel` are never allowed.
+    attributes: HashMap<String, HashSet<String>>,
+    /// The allowed schemes of URLs in links and images, relative URLs are always allowed.
+    url_schemes: HashSet<String>,
+    /// The `rel` every link is given, none if empty.
+    link_rel: String,
+    /// The CSS properties allowed in `style` attributes, which the syntax highlighter colors code with.
+    style_properties: HashSet<String>,
+}
+
+fn set(items: &[&str]) -> HashSet<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        SanitizePolicy {
+            tags: set(&[
+                "a",
+                "blockquote",
+                "br",
+                "code",
+                "del",
+                "em",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "hr",
+                "img",
+                "li",
+                "ol",
+                "p",
+                "pre",
+                "span",
+                "strong",
+                "sub",
+                "sup",
+                "table",
+                "tbody",
+                "td",
+                "th",
+                "thead",
+                "tr",
+                "ul",
+            ]),
+            attributes: [
+                ("a", set(&["href", "title"])),
+                ("img", set(&["src", "alt", "title"])),
+                ("code", set(&["class"])),
+                ("pre", set(&["style"])),
+                ("span", set(&["style"])),
+                ("ol", set(&["start"])),
+            ]
+            .into_iter()
+            .map(|(tag, attributes)| (tag.to_string(), attributes))
+            .collect(),
+            url_schemes: set(&["http", "https", "mailto"]),
+            link_rel: "nofollow ugc".into(),
+            style_properties: set(&[
+                "color",
+                "background-color",
+                "font-style",
+                "font-weight",
+                "text-decoration",
+            ]),
+        }
+    }
+}
+
+impl SanitizePolicy {
+    /// Build the sanitizer enforcing the policy.
+    fn builder(&self) -> Builder<'_> {
+        let tags = self
+            .tags
+            .iter()
+            .map(String::as_str)
+            .filter(|tag| !CLEAN_CONTENT_TAGS.contains(tag))
+            .collect();
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(tag, attributes)| {
+                let allowed = attributes
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|a| *a != "rel" && !a.starts_with("on"))
+                    .collect();
+                (tag.as_str(), allowed)
+            })
+            .collect();
+        let mut builder = Builder::empty();
+        builder
+            .tags(tags)
+            .clean_content_tags(CLEAN_CONTENT_TAGS.iter().copied().collect())
+            .generic_attributes(HashSet::new())
+            .tag_attributes(attributes)
+            .url_schemes(self.url_schemes.iter().map(String::as_str).collect())
+            .url_relative(UrlRelative::PassThrough)
+            .link_rel(Some(self.link_rel.as_str()).filter(|rel| !rel.is_empty()))
+            .filter_style_properties(self.style_properties.iter().map(String::as_str).collect())
+            .attribute_filter(|element, attribute, value| match (element, attribute) {
+                // Only the language classes of highlighted code
+                ("code", "class") => value
+                    .strip_prefix("language-")
+                    .filter(|language| {
+                        language
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || "+#-_".contains(c))
+                    })
+                    .map(|_| Cow::Borrowed(value)),
+                _ => Some(Cow::Borrowed(value)),
+            });
+        builder
+    }
+
+    /// A hash identifying the policy, the same for equal policies whatever the order of their items.
+    fn hash(&self) -> String {
+        let sorted = |items: &HashSet<String>| {
+            let mut items: Vec<&str> = items.iter().map(String::as_str).collect();
+            items.sort_unstable();
+            items.join(" ")
+        };
+        let mut attributes: Vec<String> = self
+            .attributes
+            .iter()
+            .map(|(tag, attributes)| format!("{}={}", tag, sorted(attributes)))
+            .collect();
+        attributes.sort_unstable();
+        let canonical = [
+            sorted(&self.tags),
+            attributes.join(";"),
+            sorted(&self.url_schemes),
+            self.link_rel.clone(),
+            sorted(&self.style_properties),
+        ]
+        .join("\n");
+        format!("{:x}", Sha256::digest(canonical.as_bytes()))
+    }
+}
+
+/// The policy read from the configuration, the default one until it was read.
+static POLICY: OnceLock<SanitizePolicy> = OnceLock::new();
+static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+
+fn policy() -> &'static SanitizePolicy {
+    POLICY.get_or_init(SanitizePolicy::default)
+}
+
+/// The hash of the policy in use, stored posts rendered with another policy are rendered again.
+pub(crate) fn policy_hash() -> String {
+    policy().hash()
+}
+
+/// Remove everything from rendered HTML the policy does not allow.
+pub(crate) fn clean(html: &str) -> String {
+    SANITIZER
+        .get_or_init(|| policy().builder())
+        .clean(html)
+        .to_string()
+}
+
+/// A fairing reading the sanitization policy from the configuration.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_ignite("Sanitization", |rocket| async {
+        let policy = rocket
+            .figment()
+            .extract_inner::<SanitizePolicy>("sanitize")
+            .unwrap_or_default();
+        if POLICY.set(policy).is_err() {
+            warn!("The sanitization policy was used before it was configured");
+        }
+        rocket
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{self, Titles};
+    use comrak::ComrakOptions;
+    use rocket_dyn_templates::handlebars::Handlebars;
+
+    /// Known XSS payloads, written as raw HTML or hidden in markdown.
+    const PAYLOADS: &[&str] = &[
+        "<script>alert(1)</script>",
+        "<SCRIPT SRC=//evil.example/xss.js></SCRIPT>",
+        "<img src=x onerror=alert(1)>",
+        "<img src=\"javascript:alert(1)\">",
+        "<svg onload=alert(1)>",
+        "<svg><script>alert(1)</script></svg>",
+        "<body onload=alert(1)>",
+        "<iframe src=\"javascript:alert(1)\"></iframe>",
+        "<object data=\"javascript:alert(1)\"></object>",
+        "<embed src=\"javascript:alert(1)\">",
+        "<a href=\"javascript:alert(1)\">click</a>",
+        "<a href=\"JaVaScRiPt:alert(1)\">click</a>",
+        "<a href=\"jav&#x09;ascript:alert(1)\">click</a>",
+        "<a href=\"data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==\">click</a>",
+        "<a href=\"vbscript:msgbox(1)\">click</a>",
+        "<div style=\"background:url(javascript:alert(1))\">x</div>",
+        "<span style=\"width: expression(alert(1))\">x</span>",
+        "<style>body { background: url(javascript:alert(1)) }</style>",
+        "<form action=\"javascript:alert(1)\"><input type=submit></form>",
+        "<details open ontoggle=alert(1)>",
+        "<math><mtext><table><mglyph><style><img src=x onerror=alert(1)>",
+        "<noscript><p title=\"</noscript><img src=x onerror=alert(1)>\">",
+        "<meta http-equiv=\"refresh\" content=\"0;url=javascript:alert(1)\">",
+        "<base href=\"javascript:alert(1)//\">",
+        "<p onclick=alert(1) onmouseover=alert(1)>x</p>",
+        "\"><script>alert(1)</script>",
+        "[click](javascript:alert(1))",
+        "[click](JAVASCRIPT:alert(1))",
+        "[click](data:text/html,<script>alert(1)</script>)",
+        "![x](javascript:alert(1))",
+        "[click](\"onmouseover=alert(1))",
+        "<javascript:alert(1)>",
+        "```html\n<script>alert(1)</script>\n```",
+        "`<img src=x onerror=alert(1)>`",
+    ];
+
+    /// Whether rendered HTML contains anything that runs script.
+    fn is_dangerous(html: &str) -> bool {
+        let html = html.to_lowercase();
+        [
+            "<script", "<svg", "<iframe", "<object", "<embed", "<style", "<form", "<meta", "<base",
+        ]
+        .iter()
+        .any(|tag| html.contains(tag))
+            || ["javascript:", "vbscript:", "data:text", "expression("]
+                .iter()
+                .any(|scheme| html.contains(scheme) && !is_escaped_text(&html, scheme))
+            || html.split('<').skip(1).any(|tag| {
+                let tag = tag.split('>').next().unwrap_or_default();
+                tag.split_whitespace().skip(1).any(|a| a.starts_with("on"))
+            })
+    }
+
+    /// Whether every occurrence of the needle is in text rather than in an attribute.
+    fn is_escaped_text(html: &str, needle: &str) -> bool {
+        html.match_indices(needle).all(|(i, _)| {
+            let before = &html[..i];
+            before.rfind('>') > before.rfind('<')
+        })
+    }
+
+    /// Render markdown like questions and answers are rendered, with raw HTML let through by comrak.
+    fn render_unsafe(md: &str) -> String {
+        let mut options = ComrakOptions::default();
+        options.render.unsafe_ = true;
+        markdown::render_with_options(md, &Titles::new(), &options)
+    }
+
+    #[test]
+    fn questions_and_answers_are_safe() {
+        for payload in PAYLOADS {
+            let html = markdown::render(payload, &Titles::new());
+            assert!(!is_dangerous(&html), "{:?} rendered to {:?}", payload, html);
+        }
+    }
+
+    #[test]
+    fn raw_html_is_sanitized() {
+        for payload in PAYLOADS {
+            let html = render_unsafe(payload);
+            assert!(!is_dangerous(&html), "{:?} rendered to {:?}", payload, html);
+        }
+    }
+
+    #[test]
+    fn payloads_inside_question_references_are_escaped() {
+        let titles: Titles = PAYLOADS
+            .iter()
+            .enumerate()
+            .map(|(id, payload)| (id as i32, payload.to_string()))
+            .collect();
+        let md: String = (0..PAYLOADS.len()).map(|id| format!("#{} ", id)).collect();
+        let html = markdown::render(&md, &titles);
+        assert!(!is_dangerous(&html), "rendered to {:?}", html);
+    }
+
+    #[test]
+    fn tag_descriptions_are_escaped() {
+        let mut handlebars = Handlebars::new();
+        for (name, path) in [
+            ("layout", "templates/layout.hbs"),
+            ("questions", "templates/questions.html.hbs"),
+        ] {
+            handlebars.register_template_file(name, path).unwrap();
+        }
+        handlebars.register_helper("to_duration", Box::new(crate::datetime_helper));
+        for payload in PAYLOADS {
+            let page = handlebars
+                .render(
+                    "questions",
+                    &serde_json::json!({
+                        "title": "tag",
+                        "description": payload,
+                        "questions": [],
+                        "all_tags": [],
+                        "selected_tags": [],
+                        "num_questions": 0,
+                    }),
+                )
+                .unwrap();
+            // The layout has scripts of its own, only the description is checked
+            let start = page.find("<p class=\"mx-4 lead\">").unwrap();
+            let html = &page[start..start + page[start..].find("</p>").unwrap()];
+            assert!(!is_dangerous(html), "{:?} rendered to {:?}", payload, html);
+        }
+    }
+
+    #[test]
+    fn links_are_nofollow() {
+        let html = render_unsafe("[a](https://example.org) <a href=\"/q/1\" rel=\"me\">b</a>");
+        assert_eq!(html.matches("rel=\"nofollow ugc\"").count(), 2, "{}", html);
+        assert!(!html.contains("rel=\"me\""), "{}", html);
+    }
+
+    #[test]
+    fn rendered_markdown_is_kept() {
+        let html = markdown::render(
+            "# Title\n\n*a* **b** `c` E0382 [d](https://example.org)\n\n```rust\nfn main() {}\n```",
+            &Titles::new(),
+        );
+        for expected in [
+            "<h1>Title</h1>",
+            "<em>a</em>",
+            "<strong>b</strong>",
+            "<code>c</code>",
+            "href=\"https://doc.rust-lang.org/error_codes/E0382.html\"",
+            "<code class=\"language-rust\">",
+            "<span style=\"color:#268bd2\">",
+        ] {
+            assert!(html.contains(expected), "{} missing in {}", expected, html);
+        }
+    }
+}
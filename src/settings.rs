@@ -0,0 +1,229 @@
+use crate::auth::{hash_token, random_token};
+use crate::backend::send_verification;
+use crate::db::models::{
+    ApiToken, Digest, EmailSettings, Login, NewApiToken, NotificationSettings, Role, Scope, Tag,
+};
+use crate::db::DbConn;
+use crate::mail::Mail;
+use crate::oidc::OidcConfig;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+struct SettingsCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    notifications: NotificationSettings,
+    email: EmailSettings,
+    tags: Vec<TagChoice>,
+    hide_ignored: bool,
+    scopes: Vec<&'static str>,
+    tokens: Vec<ApiToken>,
+    /// A token that was just created, it is only shown once.
+    new_token: Option<String>,
+    /// Whether links to the administration pages are shown.
+    admin: bool,
+}
+
+/// A tag that can be watched to be notified and receive digests about it, or ignored to hide its questions.
+#[derive(Debug, Clone, Serialize)]
+struct TagChoice {
+    tag: Tag,
+    watched: bool,
+    ignored: bool,
+}
+
+async fn render_settings(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    new_token: Option<String>,
+) -> Result<Template, (Status, String)> {
+    let watched_tags = conn.followed_tags(user.id).await?;
+    let ignored_tags = conn.ignored_tags(user.id).await?;
+    Ok(Template::render(
+        "settings",
+        SettingsCtx {
+            tokens: conn.api_tokens(user.id).await?,
+            unread: conn.unread_notifications(user.id).await?,
+            notifications: conn.notification_settings(user.id).await?,
+            email: conn.email_settings(user.id).await?,
+            tags: conn
+                .all_tags()
+                .await?
+                .into_iter()
+                .map(|tag| TagChoice {
+                    watched: watched_tags.contains(&tag.id),
+                    ignored: ignored_tags.contains(&tag.id),
+                    tag,
+                })
+                .collect(),
+            hide_ignored: conn.hides_ignored(user.id).await?,
+            admin: conn.user(user.id).await?.role == Role::Admin,
+            user: Some(user.username),
+            sso: oidc.is_some(),
+
+            scopes: Scope::ALL.iter().map(Scope::as_str).collect(),
+            new_token,
+        },
+    ))
+}
+
+#[get("/settings")]
+pub(crate) async fn settings(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    render_settings(user, conn, oidc, None).await
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct TokenForm {
+    name: String,
+    scopes: Vec<String>,
+}
+
+#[post("/settings/tokens", data = "<token>")]
+pub(crate) async fn create_token(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    token: Form<TokenForm>,
+) -> Result<Template, (Status, String)> {
+    let TokenForm { name, scopes } = token.into_inner();
+    if name.trim().is_empty() {
+        return Err((Status::BadRequest, "A token needs a name".into()));
+    }
+    let scopes: Vec<&str> = Scope::parse_list(&scopes.join(" "))
+        .iter()
+        .map(Scope::as_str)
+        .collect();
+
+    let secret = format!("ro_{}", random_token(40));
+    conn.new_api_token(NewApiToken {
+        user: user.id,
+        name,
+        hash: hash_token(&secret),
+        scopes: scopes.join(" "),
+    })
+    .await?;
+    render_settings(user, conn, oidc, Some(secret)).await
+}
+
+#[post("/settings/tokens/<id>/revoke")]
+pub(crate) async fn revoke_token(
+    user: Login,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.revoke_api_token(user.id, id).await?;
+    Ok(Redirect::to(uri!(settings)))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct NotificationsForm {
+    answers: bool,
+    accepted: bool,
+    votes: bool,
+    questions: bool,
+}
+
+#[post("/settings/notifications", data = "<settings>")]
+pub(crate) async fn notifications(
+    user: Login,
+    conn: DbConn,
+    settings: Form<NotificationsForm>,
+) -> Result<Redirect, (Status, String)> {
+    let NotificationsForm {
+        answers,
+        accepted,
+        votes,
+        questions,
+    } = settings.into_inner();
+    conn.set_notification_settings(
+        user.id,
+        NotificationSettings {
+            answers,
+            accepted,
+            votes,
+            questions,
+        },
+    )
+    .await?;
+    Ok(Redirect::to(uri!(settings)))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct EmailForm {
+    email: String,
+}
+
+#[post("/settings/email", data = "<form>")]
+pub(crate) async fn change_email(
+    user: Login,
+    conn: DbConn,
+    mail: &State<Mail>,
+    form: Form<EmailForm>,
+) -> Result<Redirect, (Status, String)> {
+    let address = form.into_inner().email.trim().to_string();
+    if address.parse::<lettre::Address>().is_err() {
+        return Err((Status::BadRequest, "Invalid email address".into()));
+    }
+    conn.change_email(user.id, address).await?;
+    send_verification(&conn, mail, &user).await?;
+    Ok(Redirect::to(uri!(settings)))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct SubscriptionsForm {
+    answers: bool,
+    digest: Digest,
+}
+
+#[post("/settings/subscriptions", data = "<form>")]
+pub(crate) async fn subscriptions(
+    user: Login,
+    conn: DbConn,
+    form: Form<SubscriptionsForm>,
+) -> Result<Redirect, (Status, String)> {
+    let SubscriptionsForm { answers, digest } = form.into_inner();
+    conn.set_email_settings(user.id, answers, digest).await?;
+    Ok(Redirect::to(uri!(settings)))
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct TagsForm {
+    watched: Vec<i32>,
+    ignored: Vec<i32>,
+    hide_ignored: bool,
+}
+
+#[post("/settings/tags", data = "<form>")]
+pub(crate) async fn tags(
+    user: Login,
+    conn: DbConn,
+    form: Form<TagsForm>,
+) -> Result<Redirect, (Status, String)> {
+    let TagsForm {
+        watched,
+        ignored,
+        hide_ignored,
+    } = form.into_inner();
+    if watched.iter().any(|t| ignored.contains(t)) {
+        return Err((
+            Status::BadRequest,
+            "A tag cannot be watched and ignored at once".into(),
+        ));
+    }
+    conn.set_followed_tags(user.id, watched).await?;
+    conn.set_ignored_tags(user.id, ignored).await?;
+    conn.set_hides_ignored(user.id, hide_ignored).await?;
+    Ok(Redirect::to(uri!(settings)))
+}
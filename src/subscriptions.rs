@@ -0,0 +1,252 @@
+use crate::db::models::{Login, Subscriber};
+use crate::db::{DbConn, TaskConn};
+use crate::frontend::{rocket_uri_macro_profile, rocket_uri_macro_thread};
+use crate::mail::Mail;
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use chrono::Local;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+use std::time::Duration;
+
+/// How often the scheduler looks for digests that are due.
+const DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The link to the unsubscribe page of the recipient.
+fn unsubscribe_link(mail: &Mail, subscriber: &Subscriber) -> String {
+    mail.link(&uri!(unsubscribe(token = &subscriber.unsubscribe_token)).to_string())
+}
+
+/// The footer of every email, linking to the unsubscribe page of the recipient.
+fn footer(mail: &Mail, subscriber: &Subscriber) -> String {
+    format!(
+        "\n--\nYou can stop all emails from this board by opening the following link:\n{}\n",
+        unsubscribe_link(mail, subscriber)
+    )
+}
+
+/// Email the followers of a question about a new answer.
+/// The emails are sent in the background, failures are only logged as the answer is already stored.
+pub(crate) async fn answer_emails(conn: &DbConn, mail: &Mail, author: &Login, answer: i32) {
+    let result = async {
+        let answer = conn.answer(answer).await?;
+        let question = conn.question(answer.question).await?;
+        let subscribers = conn.answer_subscribers(question.id, author.id).await?;
+        Ok::<_, (Status, String)>((answer, question, subscribers))
+    }
+    .await;
+    let (answer, question, subscribers) = match result {
+        Ok(found) => found,
+        Err((_, e)) => {
+            warn!("Could not find the recipients of answer emails: {}", e);
+            return;
+        }
+    };
+
+    let mail = mail.clone();
+    let link = mail.link(&format!(
+        "{}#answer-{}",
+        uri!(thread(id = question.id)),
+        answer.id
+    ));
+    rocket::tokio::spawn(async move {
+        for subscriber in subscribers {
+            let body = format!(
+                "Hello {},\n\n{} answered the question \"{}\":\n\n{}\n\n{}\n{}",
+                subscriber.username,
+                answer.author,
+                question.title,
+                answer.text,
+                link,
+                footer(&mail, &subscriber)
+            );
+            let subject = format!("New answer to \"{}\"", question.title);
+            if let Some(address) = &subscriber.email {
+                let unsubscribe = unsubscribe_link(&mail, &subscriber);
+                if let Err(e) = mail
+                    .send_to_subscriber(address, &subject, body, unsubscribe)
+                    .await
+                {
+                    warn!("Could not send answer email: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Send the digests that are due, i.e. whose period passed since the last one.
+async fn send_digests(conn: &DbConn, mail: &Mail) -> Result<(), (Status, String)> {
+    let now = Local::now().naive_local();
+    for subscriber in conn.digest_subscribers().await? {
+        let period = match subscriber.digest.period() {
+            Some(period) => period,
+            None => continue,
+        };
+        let since = match subscriber.last_digest {
+            Some(last) if last + period > now => continue,
+            Some(last) => last,
+            None => now - period,
+        };
+        let address = match &subscriber.email {
+            Some(address) => address,
+            None => continue,
+        };
+
+        let questions = conn.followed_tags_questions(subscriber.id, since).await?;
+        if !questions.is_empty() {
+            let list: String = questions
+                .iter()
+                .map(|q| {
+                    format!(
+                        "* {} asked by {}\n  {}\n",
+                        q.title,
+                        q.author,
+                        mail.link(&uri!(thread(id = q.id)).to_string())
+                    )
+                })
+                .collect();
+            let body = format!(
+                "Hello {},\n\nthese questions were asked in the tags you follow:\n\n{}{}",
+                subscriber.username,
+                list,
+                footer(mail, &subscriber)
+            );
+            let subject = format!("{} new questions on RustOverflow", questions.len());
+            let unsubscribe = unsubscribe_link(mail, &subscriber);
+            if let Err(e) = mail
+                .send_to_subscriber(address, &subject, body, unsubscribe)
+                .await
+            {
+                warn!("Could not send digest: {}", e);
+                continue;
+            }
+        }
+        conn.mark_digest_sent(subscriber.id, now).await?;
+    }
+    Ok(())
+}
+
+/// A fairing starting the scheduler that sends the daily and weekly digests.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Digests", |rocket| {
+        Box::pin(async move {
+            let (conn, mail) = match (rocket.state::<TaskConn>(), rocket.state::<Mail>()) {
+                (Some(conn), Some(mail)) => (conn.clone(), mail.clone()),
+                _ => {
+                    error!("Digests need a database connection and a mailer");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(DIGEST_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err((_, e)) = send_digests(&conn, &mail).await {
+                        warn!("Could not send digests: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
+
+#[post("/q/<qid>/follow")]
+pub(crate) async fn follow(
+    user: Login,
+    conn: DbConn,
+    qid: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.set_follows_question(user.id, qid, true).await?;
+    Ok(Redirect::to(uri!(thread(id = qid))))
+}
+
+#[post("/q/<qid>/unfollow")]
+pub(crate) async fn unfollow(
+    user: Login,
+    conn: DbConn,
+    qid: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.set_follows_question(user.id, qid, false).await?;
+    Ok(Redirect::to(uri!(thread(id = qid))))
+}
+
+#[post("/u/<name>/follow")]
+pub(crate) async fn follow_user(
+    user: Login,
+    conn: DbConn,
+    name: String,
+) -> Result<Redirect, (Status, String)> {
+    let other = conn.user_with_name(name).await?;
+    conn.set_follows_user(user.id, other.id, true).await?;
+    Ok(Redirect::to(uri!(profile(name = other.username))))
+}
+
+#[post("/u/<name>/unfollow")]
+pub(crate) async fn unfollow_user(
+    user: Login,
+    conn: DbConn,
+    name: String,
+) -> Result<Redirect, (Status, String)> {
+    let other = conn.user_with_name(name).await?;
+    conn.set_follows_user(user.id, other.id, false).await?;
+    Ok(Redirect::to(uri!(profile(name = other.username))))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UnsubscribeCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    token: String,
+}
+
+/// Ask to confirm stopping all emails to a user, linked from every email so it works without logging in.
+/// Nothing changes on opening the link, as mail scanners open it too.
+#[get("/unsubscribe/<token>")]
+pub(crate) async fn unsubscribe(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    token: String,
+) -> Result<Template, (Status, String)> {
+    Ok(Template::render(
+        "unsubscribe",
+        UnsubscribeCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+            token,
+        },
+    ))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UnsubscribedCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+}
+
+/// Stop all emails to a user, posted by the confirmation page or by mail clients unsubscribing with one click.
+#[post("/unsubscribe/<token>")]
+pub(crate) async fn confirm_unsubscribe(
+    user: Option<Login>,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    token: String,
+) -> Result<Template, (Status, String)> {
+    conn.unsubscribe(token).await?;
+    Ok(Template::render(
+        "unsubscribed",
+        UnsubscribedCtx {
+            unread: notifications::unread(&conn, &user).await?,
+            user: user.map(|u| u.username),
+            sso: oidc.is_some(),
+        },
+    ))
+}
@@ -0,0 +1,153 @@
+use crate::db::models::Tag;
+use crate::db::DbConn;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use std::collections::HashMap;
+
+/// How many tags are suggested at most.
+const SUGGESTION_LIMIT: usize = 5;
+/// Tags scoring less than this are not suggested.
+const MIN_SCORE: u32 = 2;
+/// How much a dependency in a `Cargo.toml` snippet counts towards its crate.
+const DEPENDENCY_WEIGHT: u32 = 4;
+/// How much an import, `extern crate` or a path starting with a crate counts towards it.
+const PATH_WEIGHT: u32 = 3;
+/// How much an attribute, derive or syntax belonging to a crate counts towards it.
+const MARKER_WEIGHT: u32 = 2;
+/// How much mentioning the name of a crate counts towards it.
+const KEYWORD_WEIGHT: u32 = 1;
+/// Path roots which are no crates of their own.
+const NOT_CRATES: &[&str] = &["std", "core", "alloc", "crate", "self", "super", "Self"];
+/// Attributes, derives and template syntax together with the crate they belong to.
+const MARKERS: &[(&str, &str)] = &[
+    ("#[launch]", "rocket"),
+    ("#[get(", "rocket"),
+    ("#[post(", "rocket"),
+    ("#[put(", "rocket"),
+    ("#[delete(", "rocket"),
+    ("#[catch(", "rocket"),
+    ("#[database(", "rocket"),
+    ("FromForm", "rocket"),
+    ("FromRequest", "rocket"),
+    ("Responder", "rocket"),
+    ("Queryable", "diesel"),
+    ("Insertable", "diesel"),
+    ("AsChangeset", "diesel"),
+    ("Identifiable", "diesel"),
+    ("Associations", "diesel"),
+    ("table!", "diesel"),
+    ("#[table_name", "diesel"),
+    ("#[diesel(", "diesel"),
+    ("{{#each", "handlebars"),
+    ("{{#if", "handlebars"),
+    ("{{#unless", "handlebars"),
+    ("{{>", "handlebars"),
+    (".hbs", "handlebars"),
+];
+
+/// Normalize a crate or tag name, crates are written with dashes and imported with underscores.
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The crates used by paths like `rocket::Request`, imports and `extern crate` in the text.
+fn paths(text: &str) -> impl Iterator<Item = String> + '_ {
+    let roots = text.match_indices("::").filter_map(move |(end, _)| {
+        let start = text[..end]
+            .rfind(|c: char| !is_ident(c))
+            .map_or(0, |i| i + 1);
+        // The empty root of absolute paths like `::rocket::Request` is skipped
+        Some(&text[start..end]).filter(|root| !root.is_empty())
+    });
+    let imports = text.lines().filter_map(|line| {
+        let line = line.trim_start();
+        let rest = line
+            .strip_prefix("use ")
+            .or_else(|| line.strip_prefix("extern crate "))?;
+        let name: String = rest.chars().take_while(|&c| is_ident(c)).collect();
+        // `use rocket::...` is already counted as a path
+        (!rest[name.len()..].starts_with("::")).then_some(name)
+    });
+    roots
+        .map(str::to_string)
+        .chain(imports)
+        .filter(|root| !NOT_CRATES.contains(&root.as_str()))
+        .map(|root| normalize(&root))
+}
+
+/// The crates declared as dependencies like `rocket = "0.5"` or `diesel = { version = "1.4" }`.
+fn dependencies(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines().filter_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        let name = name.trim();
+        let value = value.trim_start();
+        let versioned = value.starts_with('{')
+            || value
+                .strip_prefix('"')
+                .is_some_and(|v| v.starts_with(|c: char| c.is_ascii_digit() || c == '^'));
+        let valid = !name.is_empty() && name.chars().all(|c| is_ident(c) || c == '-');
+        (versioned && valid).then(|| normalize(name))
+    })
+}
+
+/// Score the crates a question is about by the clues in its title and markdown text.
+fn crate_scores(title: &str, text: &str) -> HashMap<String, u32> {
+    let mut scores: HashMap<String, u32> = HashMap::new();
+    for name in dependencies(text) {
+        *scores.entry(name).or_default() += DEPENDENCY_WEIGHT;
+    }
+    for name in paths(text) {
+        *scores.entry(name).or_default() += PATH_WEIGHT;
+    }
+    for (marker, name) in MARKERS {
+        if text.contains(marker) {
+            *scores.entry(name.to_string()).or_default() += MARKER_WEIGHT;
+        }
+    }
+    for word in title
+        .split(|c: char| !is_ident(c) && c != '-')
+        .chain(text.split(|c: char| !is_ident(c) && c != '-'))
+    {
+        if !word.is_empty() {
+            *scores.entry(normalize(word)).or_default() += KEYWORD_WEIGHT;
+        }
+    }
+    scores
+}
+
+/// Suggest the tags whose names match the crates a question is about, the best matching first.
+pub(crate) fn suggest(title: &str, text: &str, tags: Vec<Tag>) -> Vec<Tag> {
+    let scores = crate_scores(title, text);
+    let mut suggested: Vec<(u32, Tag)> = tags
+        .into_iter()
+        .filter_map(|tag| {
+            let score = *scores.get(&normalize(&tag.name))?;
+            (score >= MIN_SCORE).then_some((score, tag))
+        })
+        .collect();
+    suggested.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    suggested.truncate(SUGGESTION_LIMIT);
+    suggested.into_iter().map(|(_, tag)| tag).collect()
+}
+
+/// The fields of the ask form the tags are suggested from.
+#[derive(Debug, FromForm)]
+pub(crate) struct SuggestForm {
+    title: String,
+    question: String,
+}
+
+/// Suggest tags for the question being asked, queried by the ask form while typing.
+#[post("/ask/tags", data = "<form>")]
+pub(crate) async fn suggest_tags(
+    conn: DbConn,
+    form: Form<SuggestForm>,
+) -> Result<Json<Vec<Tag>>, (Status, String)> {
+    let SuggestForm { title, question } = form.into_inner();
+    Ok(Json(suggest(&title, &question, conn.all_tags().await?)))
+}
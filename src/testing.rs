@@ -0,0 +1,74 @@
+//! Helpers shared by the tests.
+
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::sync::mpsc;
+
+/// A request received by a local server.
+pub(crate) struct Received {
+    pub(crate) method: String,
+    /// The path of the request, including its query.
+    pub(crate) path: String,
+    headers: String,
+    pub(crate) body: String,
+}
+
+impl Received {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+}
+
+/// Read a request up to the end of its body.
+async fn read_request(socket: &mut rocket::tokio::net::TcpStream) -> Received {
+    let mut request = vec![];
+    let mut buffer = [0; 4096];
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).into_owned();
+        if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+            let mut line = headers.lines().next().unwrap_or_default().split(' ');
+            let received = Received {
+                method: line.next().unwrap_or_default().to_string(),
+                path: line.next().unwrap_or_default().to_string(),
+                headers: headers.to_string(),
+                body: body.to_string(),
+            };
+            let length = received.header("Content-Length").unwrap_or("0");
+            if received.body.len() >= length.parse::<usize>().unwrap() || read == 0 {
+                return received;
+            }
+        }
+    }
+}
+
+/// Serve HTTP on a local port, answering every request with the status and the JSON body returned by the handler.
+/// Returns the url of the server and the requests it received.
+pub(crate) async fn serve<F>(mut handler: F) -> (String, mpsc::UnboundedReceiver<Received>)
+where
+    F: FnMut(&Received) -> (u16, String) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, received) = mpsc::unbounded_channel();
+    rocket::tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut socket).await;
+            let (status, body) = handler(&request);
+            let _ = sender.send(request);
+            let response = format!(
+                "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (url, received)
+}
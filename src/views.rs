@@ -0,0 +1,128 @@
+use crate::db::models::Login;
+use crate::db::TaskConn;
+use rocket::fairing::AdHoc;
+use rocket::outcome::IntoOutcome;
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often the counted views are written to the database.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// A viewer is counted once per question within this time.
+const VIEW_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// User agents containing one of these belong to crawlers, whose views are not counted.
+const CRAWLERS: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "mediapartners",
+    "bingpreview",
+    "headlesschrome",
+];
+
+/// Someone viewing a page, logged in users are told apart by their id and everyone else by their address.
+/// Crawlers are forwarded, so they are no viewers.
+pub(crate) struct Viewer(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Viewer {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let agent = request
+            .headers()
+            .get_one("User-Agent")
+            .unwrap_or_default()
+            .to_lowercase();
+        if agent.is_empty() || CRAWLERS.iter().any(|crawler| agent.contains(crawler)) {
+            return Outcome::Forward(());
+        }
+        match request.guard::<Login>().await.succeeded() {
+            Some(user) => Outcome::Success(Viewer(format!("user:{}", user.id))),
+            None => request
+                .client_ip()
+                .map(|ip| Viewer(format!("ip:{}", ip)))
+                .or_forward(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Views {
+    /// When each viewer was last counted for a question.
+    seen: HashMap<(i32, String), Instant>,
+    /// The views of each question not written to the database yet.
+    pending: HashMap<i32, i32>,
+}
+
+/// Counts the views of questions in memory, they are written to the database in batches.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ViewCounter(Arc<Mutex<Views>>);
+
+impl ViewCounter {
+    /// Count a view of a question, unless the viewer was already counted within the window.
+    pub(crate) fn record(&self, question: i32, viewer: Viewer) {
+        let now = Instant::now();
+        let mut views = self.0.lock().expect("view counter poisoned");
+        let key = (question, viewer.0);
+        if let Some(last) = views.seen.get(&key) {
+            if now - *last < VIEW_WINDOW {
+                return;
+            }
+        }
+        views.seen.insert(key, now);
+        *views.pending.entry(question).or_default() += 1;
+    }
+
+    /// Take the views to write and forget the viewers whose window passed.
+    fn take(&self) -> HashMap<i32, i32> {
+        let now = Instant::now();
+        let mut views = self.0.lock().expect("view counter poisoned");
+        views.seen.retain(|_, last| now - *last < VIEW_WINDOW);
+        std::mem::take(&mut views.pending)
+    }
+
+    /// Add views which could not be written back to the ones to write next time.
+    fn restore(&self, pending: HashMap<i32, i32>) {
+        let mut views = self.0.lock().expect("view counter poisoned");
+        for (question, count) in pending {
+            *views.pending.entry(question).or_default() += count;
+        }
+    }
+}
+
+/// A fairing starting the task that writes the counted views to the database.
+/// Views counted since the last write are lost when the server stops.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Views", |rocket| {
+        Box::pin(async move {
+            let (conn, counter) = match (rocket.state::<TaskConn>(), rocket.state::<ViewCounter>())
+            {
+                (Some(conn), Some(counter)) => (conn.clone(), counter.clone()),
+                _ => {
+                    error!("Views need a database connection and a counter");
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(FLUSH_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let pending = counter.take();
+                    if pending.is_empty() {
+                        continue;
+                    }
+                    // The views are written in one transaction, so none were stored if it failed
+                    if let Err((_, e)) = conn.add_views(pending.clone()).await {
+                        warn!("Could not store views: {}", e);
+                        counter.restore(pending);
+                    }
+                }
+            });
+        })
+    })
+}
@@ -0,0 +1,525 @@
+use crate::auth::random_token;
+use crate::backend::Admin;
+use crate::db::models::{Delivery, DeliveryStatus, Login, NewWebhook, Tag, Webhook, WebhookEvent};
+use crate::db::{DbConn, TaskConn};
+use crate::frontend::rocket_uri_macro_thread;
+use crate::mail::Mail;
+use crate::notifications;
+use crate::oidc::OidcConfig;
+use chrono::{Local, NaiveDateTime};
+use hmac::{Hmac, Mac};
+use rocket::fairing::AdHoc;
+use rocket::form::Form;
+use rocket::http::Status;
+use rocket::response::Redirect;
+use rocket::State;
+use rocket_dyn_templates::Template;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// How often the dispatcher looks for deliveries that are due.
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a receiver may take to answer a delivery.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// After this many failed attempts a delivery is given up.
+const MAX_ATTEMPTS: i32 = 6;
+/// The delay before the first retry, it doubles with every further attempt.
+const FIRST_RETRY_SECS: i64 = 30;
+
+/// The body posted to a webhook.
+#[derive(Debug, Clone, Serialize)]
+struct Payload {
+    event: WebhookEvent,
+    question: QuestionPayload,
+    answer: Option<AnswerPayload>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct QuestionPayload {
+    id: i32,
+    url: String,
+    title: String,
+    author: String,
+    tags: Vec<String>,
+    time: NaiveDateTime,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AnswerPayload {
+    id: i32,
+    url: String,
+    author: String,
+    accepted: bool,
+    time: NaiveDateTime,
+    text: String,
+}
+
+/// Enqueue the deliveries of an event about a question, or an answer to it, to all webhooks interested in it.
+/// Failures are only logged as the event itself is already stored.
+pub(crate) async fn fire(
+    conn: &DbConn,
+    mail: &Mail,
+    event: WebhookEvent,
+    question: i32,
+    answer: Option<i32>,
+) {
+    let result = async {
+        let question = conn.question(question).await?;
+        let answer = match answer {
+            Some(answer) => Some(conn.answer(answer).await?),
+            None => None,
+        };
+        let link = mail.link(&uri!(thread(id = question.id)).to_string());
+        let payload = Payload {
+            event,
+            answer: answer.map(|answer| AnswerPayload {
+                url: format!("{}#answer-{}", link, answer.id),
+                id: answer.id,
+                author: answer.author,
+                accepted: answer.accepted,
+                time: answer.time,
+                text: answer.text,
+            }),
+            question: QuestionPayload {
+                id: question.id,
+                url: link,
+                title: question.title,
+                author: question.author,
+                tags: question.tags.iter().map(|t| t.name.clone()).collect(),
+                time: question.time,
+                text: question.text,
+            },
+        };
+        let payload = serde_json::to_string(&payload)
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+        let tags = question.tags.iter().map(|t| t.id).collect();
+        conn.enqueue_deliveries(event, tags, payload).await
+    }
+    .await;
+    if let Err((_, e)) = result {
+        warn!("Could not enqueue webhook deliveries: {}", e);
+    }
+}
+
+/// Sign a payload with the secret of its webhook, receivers compare it to the `X-RustOverflow-Signature` header.
+fn sign(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// Post a delivery to its webhook, returning the status of the response and the error if it failed.
+async fn attempt(
+    client: &reqwest::Client,
+    delivery: &Delivery,
+    webhook: &Webhook,
+) -> (Option<i32>, Option<String>) {
+    let response = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "RustOverflow-Webhooks")
+        .header("X-RustOverflow-Event", delivery.event.as_str())
+        .header("X-RustOverflow-Delivery", delivery.id.to_string())
+        .header(
+            "X-RustOverflow-Signature",
+            sign(&webhook.secret, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status().is_success() => {
+            (Some(response.status().as_u16().into()), None)
+        }
+        Ok(response) => (
+            Some(response.status().as_u16().into()),
+            Some(format!("The receiver answered with {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// The status of a delivery after the given number of attempts, failed ones are retried until they ran out of attempts.
+fn status_after(attempts: i32, failed: bool) -> DeliveryStatus {
+    match failed {
+        false => DeliveryStatus::Delivered,
+        true if attempts >= MAX_ATTEMPTS => DeliveryStatus::Failed,
+        true => DeliveryStatus::Pending,
+    }
+}
+
+/// The delay before the next attempt of a delivery that failed the given number of times.
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    chrono::Duration::seconds(FIRST_RETRY_SECS << (attempts - 1).clamp(0, 16))
+}
+
+/// Attempt all deliveries that are due, failed ones are retried with an exponential backoff.
+async fn dispatch(conn: &DbConn, client: &reqwest::Client) -> Result<(), (Status, String)> {
+    for (delivery, webhook) in conn.due_deliveries(Local::now().naive_local()).await? {
+        let (response, error) = attempt(client, &delivery, &webhook).await;
+        let attempts = delivery.attempts + 1;
+        let status = status_after(attempts, error.is_some());
+        let next = Local::now().naive_local() + retry_delay(attempts);
+        conn.record_attempt(delivery.id, status, next, response, error)
+            .await?;
+    }
+    Ok(())
+}
+
+/// A fairing starting the dispatcher that works through the queue of webhook deliveries.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Webhooks", |rocket| {
+        Box::pin(async move {
+            let conn = match rocket.state::<TaskConn>() {
+                Some(conn) => conn.clone(),
+                None => {
+                    error!("Webhooks need a database connection");
+                    return;
+                }
+            };
+            let client = match reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build() {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Could not create the webhook client: {}", e);
+                    return;
+                }
+            };
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(DISPATCH_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err((_, e)) = dispatch(&conn, &client).await {
+                        warn!("Could not dispatch webhooks: {}", e);
+                    }
+                }
+            });
+        })
+    })
+}
+
+/// A webhook together with the tags it is filtered by.
+#[derive(Debug, Clone, Serialize)]
+struct WebhookView {
+    webhook: Webhook,
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct WebhooksCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    webhooks: Vec<WebhookView>,
+    tags: Vec<Tag>,
+    /// The secret of a webhook that was just created, it is only shown once.
+    new_secret: Option<String>,
+}
+
+async fn render_webhooks(
+    user: Login,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    new_secret: Option<String>,
+) -> Result<Template, (Status, String)> {
+    let mut tags = conn.webhook_tags().await?;
+    Ok(Template::render(
+        "webhooks",
+        WebhooksCtx {
+            webhooks: conn
+                .webhooks()
+                .await?
+                .into_iter()
+                .map(|webhook| WebhookView {
+                    tags: tags.remove(&webhook.id).unwrap_or_default(),
+                    webhook,
+                })
+                .collect(),
+            tags: conn.all_tags().await?,
+            unread: notifications::unread(&conn, &Some(user.clone())).await?,
+            user: Some(user.username),
+            sso: oidc.is_some(),
+            new_secret,
+        },
+    ))
+}
+
+#[get("/admin/webhooks")]
+pub(crate) async fn webhooks(
+    admin: Admin,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+) -> Result<Template, (Status, String)> {
+    render_webhooks(admin.0, conn, oidc, None).await
+}
+
+#[derive(Debug, FromForm)]
+pub(crate) struct WebhookForm {
+    url: String,
+    /// Left empty to generate a random secret.
+    secret: String,
+    on_question: bool,
+    on_answer: bool,
+    on_accepted: bool,
+    tags: Vec<i32>,
+}
+
+#[post("/admin/webhooks", data = "<form>")]
+pub(crate) async fn create_webhook(
+    admin: Admin,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    form: Form<WebhookForm>,
+) -> Result<Template, (Status, String)> {
+    let user = admin.0;
+    let WebhookForm {
+        url,
+        secret,
+        on_question,
+        on_answer,
+        on_accepted,
+        tags,
+    } = form.into_inner();
+    let url = url.trim().to_string();
+    match reqwest::Url::parse(&url) {
+        Ok(parsed) if ["http", "https"].contains(&parsed.scheme()) => {}
+        _ => return Err((Status::BadRequest, "Invalid webhook url".into())),
+    }
+    let secret = match secret.trim() {
+        "" => random_token(32),
+        secret => secret.to_string(),
+    };
+
+    conn.new_webhook(
+        NewWebhook {
+            url,
+            secret: secret.clone(),
+            on_question,
+            on_answer,
+            on_accepted,
+        },
+        tags,
+    )
+    .await?;
+    render_webhooks(user, conn, oidc, Some(secret)).await
+}
+
+#[post("/admin/webhooks/<id>/delete")]
+pub(crate) async fn delete_webhook(
+    _admin: Admin,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.delete_webhook(id).await?;
+    Ok(Redirect::to(uri!(webhooks)))
+}
+
+#[post("/admin/webhooks/<id>/pause")]
+pub(crate) async fn pause_webhook(
+    _admin: Admin,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.set_webhook_active(id, false).await?;
+    Ok(Redirect::to(uri!(webhooks)))
+}
+
+#[post("/admin/webhooks/<id>/resume")]
+pub(crate) async fn resume_webhook(
+    _admin: Admin,
+    conn: DbConn,
+    id: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.set_webhook_active(id, true).await?;
+    Ok(Redirect::to(uri!(webhooks)))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct DeliveriesCtx {
+    user: Option<String>,
+    sso: bool,
+    unread: i64,
+
+    webhook: Webhook,
+    deliveries: Vec<Delivery>,
+}
+
+/// The delivery log of a webhook.
+#[get("/admin/webhooks/<id>")]
+pub(crate) async fn deliveries(
+    admin: Admin,
+    conn: DbConn,
+    oidc: &State<Option<OidcConfig>>,
+    id: i32,
+) -> Result<Template, (Status, String)> {
+    let user = admin.0;
+    Ok(Template::render(
+        "deliveries",
+        DeliveriesCtx {
+            webhook: conn.webhook(id).await?,
+            deliveries: conn.deliveries(id).await?,
+            unread: notifications::unread(&conn, &Some(user.clone())).await?,
+            user: Some(user.username),
+            sso: oidc.is_some(),
+        },
+    ))
+}
+
+#[post("/admin/webhooks/<hook>/deliveries/<delivery>/retry")]
+pub(crate) async fn redeliver(
+    _admin: Admin,
+    conn: DbConn,
+    hook: i32,
+    delivery: i32,
+) -> Result<Redirect, (Status, String)> {
+    conn.redeliver(hook, delivery).await?;
+    Ok(Redirect::to(uri!(deliveries(id = hook))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::DeliveryStatus::{Delivered, Failed, Pending};
+    use crate::db::test_rocket;
+    use crate::testing::serve;
+
+    fn new_webhook(url: &str, on_question: bool) -> NewWebhook {
+        NewWebhook {
+            url: url.into(),
+            secret: "secret".into(),
+            on_question,
+            on_answer: true,
+            on_accepted: true,
+        }
+    }
+
+    /// Return the id of the webhook with the given url.
+    async fn webhook_id(conn: &DbConn, url: &str) -> i32 {
+        let webhooks = conn.webhooks().await.unwrap();
+        webhooks.iter().find(|w| w.url == url).unwrap().id
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // Test case 2 of RFC 4231
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn retries_with_doubling_delays() {
+        let delays: Vec<i64> = (1..=6).map(|a| retry_delay(a).num_seconds()).collect();
+        assert_eq!(delays, vec![30, 60, 120, 240, 480, 960]);
+        assert_eq!(retry_delay(100).num_seconds(), 30 << 16);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        assert_eq!(status_after(1, false), Delivered);
+        assert_eq!(status_after(MAX_ATTEMPTS, false), Delivered);
+        assert_eq!(status_after(1, true), Pending);
+        assert_eq!(status_after(MAX_ATTEMPTS - 1, true), Pending);
+        assert_eq!(status_after(MAX_ATTEMPTS, true), Failed);
+    }
+
+    #[rocket::async_test]
+    async fn enqueues_for_interested_webhooks() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        conn.new_webhook(new_webhook("http://all", true), vec![])
+            .await
+            .unwrap();
+        conn.new_webhook(new_webhook("http://answers", false), vec![])
+            .await
+            .unwrap();
+        conn.new_webhook(new_webhook("http://inactive", true), vec![])
+            .await
+            .unwrap();
+        conn.new_webhook(new_webhook("http://other-tags", true), vec![2])
+            .await
+            .unwrap();
+        conn.new_webhook(new_webhook("http://tags", true), vec![1, 3])
+            .await
+            .unwrap();
+        let inactive = webhook_id(&conn, "http://inactive").await;
+        conn.set_webhook_active(inactive, false).await.unwrap();
+
+        conn.enqueue_deliveries(WebhookEvent::QuestionCreated, vec![1], "{}".into())
+            .await
+            .unwrap();
+
+        let mut fired = vec![];
+        for webhook in conn.webhooks().await.unwrap() {
+            if !conn.deliveries(webhook.id).await.unwrap().is_empty() {
+                fired.push(webhook.url);
+            }
+        }
+        fired.sort();
+        assert_eq!(fired, vec!["http://all", "http://tags"]);
+    }
+
+    #[rocket::async_test]
+    async fn delivers_to_a_local_receiver() {
+        let rocket = test_rocket().await;
+        let conn = DbConn::get_one(&rocket).await.unwrap();
+        let mut statuses = vec![500, 200].into_iter();
+        let (url, mut received) = serve(move |_| (statuses.next().unwrap(), String::new())).await;
+        conn.new_webhook(new_webhook(&url, true), vec![])
+            .await
+            .unwrap();
+        let webhook = webhook_id(&conn, &url).await;
+        let payload = r#"{"event":"answer_created"}"#;
+        conn.enqueue_deliveries(WebhookEvent::AnswerCreated, vec![], payload.into())
+            .await
+            .unwrap();
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap();
+
+        // The receiver fails the first attempt, which is retried after the first delay
+        dispatch(&conn, &client).await.unwrap();
+        let request = received.recv().await.unwrap();
+        assert_eq!(request.body, payload);
+        assert_eq!(
+            request.header("X-RustOverflow-Signature"),
+            Some(sign("secret", payload).as_str())
+        );
+        assert_eq!(
+            request.header("X-RustOverflow-Event"),
+            Some(WebhookEvent::AnswerCreated.as_str())
+        );
+        let delivery = conn.deliveries(webhook).await.unwrap().remove(0);
+        assert_eq!(delivery.status, Pending);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.response, Some(500));
+        assert!(delivery.next_attempt > Local::now().naive_local() + retry_delay(1) / 2);
+
+        // Nothing is attempted before the retry is due
+        dispatch(&conn, &client).await.unwrap();
+        assert_eq!(conn.deliveries(webhook).await.unwrap()[0].attempts, 1);
+
+        // Make the retry due
+        conn.run(move |connection| {
+            use crate::db::schema::webhook_deliveries::dsl::*;
+            use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+            diesel::update(webhook_deliveries.filter(id.eq(delivery.id)))
+                .set(next_attempt.eq(Local::now().naive_local()))
+                .execute(connection)
+        })
+        .await
+        .unwrap();
+        dispatch(&conn, &client).await.unwrap();
+        received.recv().await.unwrap();
+        let delivery = conn.deliveries(webhook).await.unwrap().remove(0);
+        assert_eq!(delivery.status, Delivered);
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.response, Some(200));
+        assert_eq!(delivery.error, None);
+    }
+}
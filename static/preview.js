@@ -0,0 +1,24 @@
+// Render a preview of the markdown typed into every textarea with a `data-preview` attribute,
+// which names the element the preview is shown in. It is only requested once typing paused.
+document.querySelectorAll('textarea[data-preview]').forEach(textarea => {
+    const preview = document.getElementById(textarea.dataset.preview);
+    let timer;
+    let latest = 0;
+
+    textarea.addEventListener('input', () => {
+        clearTimeout(timer);
+        timer = setTimeout(() => {
+            // Responses arriving after a newer request was sent are dropped
+            const request = ++latest;
+            const body = new URLSearchParams({ text: textarea.value });
+            fetch('/preview', { method: 'POST', body })
+                .then(response => response.ok ? response.text() : Promise.reject(response.status))
+                .then(html => {
+                    if (request !== latest) return;
+                    preview.querySelector('.preview-content').innerHTML = html;
+                    preview.classList.toggle('d-none', textarea.value.trim() === '');
+                })
+                .catch(() => {});
+        }, 500);
+    });
+});